@@ -0,0 +1,167 @@
+use crate::error::TimeOutOfRange;
+use crate::time::TimeOffset;
+use crate::time::TimeStamp;
+use std::fmt;
+use std::ops::Add;
+use std::ops::Sub;
+
+/// Frames before LBA 0, which is absolute MSF `00:02:00`
+pub const LEAD_IN: u32 = 150;
+/// Frames in `00:00:00`-`99:59:74`
+const MSF_FRAMES: i32 = 100 * 60 * 75;
+/// Absolute MSF from `90:00:00` maps to negative LBAs
+const MSF_WRAP: i32 = 90 * 60 * 75;
+
+/// A logical block address, counted in frames from the start of the program area
+///
+/// Negative values point into the pregap of the first track,
+/// down to -45150 which is `90:00:00` in absolute MSF
+///
+/// ```rust
+/// use cuna::address::{AbsoluteMsf, Lba};
+/// use cuna::time::TimeStamp;
+///
+/// let lba = Lba::try_from(TimeStamp::new(3, 12, 40)).unwrap();
+/// assert_eq!(lba, Lba(14440));
+/// assert_eq!(lba.to_msf(), Some(AbsoluteMsf::new(3, 14, 40)));
+/// assert_eq!(Lba(-150).to_msf(), Some(AbsoluteMsf::new(0, 0, 0)));
+/// assert_eq!(Lba(-151).to_msf(), Some(AbsoluteMsf::new(99, 59, 74)));
+/// assert_eq!(Lba(-1).to_timestamp(), None);
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Default, PartialOrd, Ord)]
+pub struct Lba(pub i32);
+/// A position on the disc in minutes, seconds and frames, including the 2-second lead-in
+///
+/// Ranges from `00:00:00` to `99:59:74`
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Default)]
+pub struct AbsoluteMsf {
+    minutes: u8,
+    seconds: u8,
+    frames: u8,
+}
+
+impl Lba {
+    /// Returns the relative position, or `None` if `self` is negative
+    pub fn to_timestamp(&self) -> Option<TimeStamp> {
+        u64::try_from(self.0).ok().map(TimeStamp::from_frames_u64)
+    }
+    /// Returns the absolute MSF, or `None` if `self` cannot be addressed in MSF
+    pub fn to_msf(&self) -> Option<AbsoluteMsf> {
+        let frames = match self.0 {
+            lba @ -150..=404_849 => lba + LEAD_IN as i32,
+            lba @ -45150..=-151 => lba + MSF_FRAMES + LEAD_IN as i32,
+            _ => return None,
+        };
+        Some(AbsoluteMsf::from_frames_unchecked(frames as u32))
+    }
+    pub fn checked_add(self, rhs: TimeOffset) -> Option<Self> {
+        let lba = i64::from(self.0).checked_add(rhs.total_frames())?;
+        i32::try_from(lba).ok().map(Self)
+    }
+}
+impl TryFrom<TimeStamp> for Lba {
+    type Error = TimeOutOfRange;
+
+    /// Treats the timestamp as an offset from LBA 0
+    fn try_from(ts: TimeStamp) -> Result<Self, Self::Error> {
+        i32::try_from(ts.total_frames_u64())
+            .map(Self)
+            .map_err(|_| TimeOutOfRange)
+    }
+}
+impl From<AbsoluteMsf> for Lba {
+    fn from(msf: AbsoluteMsf) -> Self {
+        msf.to_lba()
+    }
+}
+impl Add<TimeOffset> for Lba {
+    type Output = Self;
+
+    fn add(self, rhs: TimeOffset) -> Self::Output {
+        self.checked_add(rhs)
+            .expect("overflow when adding offset to lba")
+    }
+}
+impl Sub for Lba {
+    type Output = TimeOffset;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        TimeOffset::from_frames(i64::from(self.0) - i64::from(rhs.0))
+    }
+}
+impl fmt::Display for Lba {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+impl AbsoluteMsf {
+    /// # Panics
+    ///
+    /// Panics if minutes >= 100, seconds >= 60 or frames >= 75
+    pub fn new(minutes: u8, seconds: u8, frames: u8) -> Self {
+        Self::new_opt(minutes, seconds, frames).expect("Invalid absolute msf")
+    }
+    pub fn new_opt(minutes: u8, seconds: u8, frames: u8) -> Option<Self> {
+        if minutes >= 100 || seconds >= 60 || frames >= 75 {
+            None
+        } else {
+            Some(Self {
+                minutes,
+                seconds,
+                frames,
+            })
+        }
+    }
+    /// Constructs from the frames since `00:00:00`, or returns `None` if not before `100:00:00`
+    pub fn from_frames(frames: u32) -> Option<Self> {
+        if frames < MSF_FRAMES as u32 {
+            Some(Self::from_frames_unchecked(frames))
+        } else {
+            None
+        }
+    }
+    const fn from_frames_unchecked(frames: u32) -> Self {
+        Self {
+            minutes: (frames / 4500) as u8,
+            seconds: (frames / 75 % 60) as u8,
+            frames: (frames % 75) as u8,
+        }
+    }
+    pub const fn minutes(&self) -> u8 {
+        self.minutes
+    }
+    pub const fn seconds(&self) -> u8 {
+        self.seconds
+    }
+    pub const fn frames(&self) -> u8 {
+        self.frames
+    }
+    pub const fn total_frames(&self) -> u32 {
+        (self.minutes as u32 * 60 + self.seconds as u32) * 75 + self.frames as u32
+    }
+    /// Returns the LBA, treating `90:00:00` and later as negative addresses
+    pub const fn to_lba(&self) -> Lba {
+        let frames = self.total_frames() as i32;
+        if frames >= MSF_WRAP {
+            Lba(frames - MSF_FRAMES - LEAD_IN as i32)
+        } else {
+            Lba(frames - LEAD_IN as i32)
+        }
+    }
+}
+impl TryFrom<Lba> for AbsoluteMsf {
+    type Error = TimeOutOfRange;
+
+    fn try_from(lba: Lba) -> Result<Self, Self::Error> {
+        lba.to_msf().ok_or(TimeOutOfRange)
+    }
+}
+impl fmt::Display for AbsoluteMsf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:0>2}:{:0>2}:{:0>2}",
+            self.minutes, self.seconds, self.frames
+        )
+    }
+}
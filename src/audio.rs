@@ -0,0 +1,389 @@
+use crate::error::TimeOutOfRange;
+use crate::time::Rounding;
+use crate::time::TimeStamp;
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::io::BufReader;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::ops::Range;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// Samples in a CD frame at 44100 Hz
+pub const SAMPLES_PER_FRAME: u64 = 588;
+
+/// The format of an audio file, found by its magic bytes
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Container {
+    /// RIFF/WAVE, including RF64 and BW64
+    Wave,
+    /// AIFF and AIFF-C
+    Aiff,
+    Flac,
+}
+/// How samples are stored in an audio file
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum SampleFormat {
+    /// Integer PCM, which FLAC always decodes to
+    Pcm,
+    /// IEEE floating point
+    Float,
+    /// A WAVE format tag or an AIFF-C compression type
+    Other(u32),
+}
+/// The stream properties read from the header of an audio file
+///
+/// ```rust
+/// use cuna::audio::{AudioInfo, Container};
+/// use cuna::time::{Rounding, TimeStamp};
+/// use std::io::Cursor;
+///
+/// let mut wav = Vec::new();
+/// wav.extend(b"RIFF\x28\x00\x00\x00WAVEfmt \x10\x00\x00\x00");
+/// wav.extend([1, 0, 2, 0, 0x44, 0xac, 0, 0, 0x10, 0xb1, 2, 0, 4, 0, 16, 0]);
+/// wav.extend(b"data\xf0\x2d\x00\x00");
+/// let info = AudioInfo::read(Cursor::new(wav)).unwrap();
+/// assert_eq!(info.container, Container::Wave);
+/// assert_eq!(info.samples, 2940);
+/// assert_eq!(info.length(Rounding::Ceil), Ok(TimeStamp::from_frames(5)));
+/// assert!(info.is_cdda());
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct AudioInfo {
+    pub container: Container,
+    pub format: SampleFormat,
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub bits_per_sample: u16,
+    /// Samples per channel
+    pub samples: u64,
+}
+/// A way in which an audio file differs from CD-DA, which is 44100 Hz, 16-bit stereo PCM
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum CddaMismatch {
+    SampleRate(u32),
+    Channels(u16),
+    BitsPerSample(u16),
+    Format(SampleFormat),
+    /// The file does not end on a frame boundary, holding the samples after the last whole frame
+    PartialFrame(u64),
+}
+
+impl AudioInfo {
+    /// Reads the header of a WAVE, RF64, AIFF, AIFF-C or FLAC stream
+    pub fn read<R: Read + Seek>(r: R) -> io::Result<Self> {
+        read_header(r).map(|(info, _)| info)
+    }
+    /// Opens a file and reads its header, see [`read()`](AudioInfo::read)
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::read(BufReader::new(File::open(path)?))
+    }
+    /// Returns the length in frames, rounding a partial frame at the end with `rounding`
+    pub fn length(&self, rounding: Rounding) -> Result<TimeStamp, TimeOutOfRange> {
+        if self.sample_rate == 0 {
+            return Err(TimeOutOfRange);
+        }
+        TimeStamp::from_samples(self.samples, self.sample_rate, rounding)
+    }
+    /// Lists how the stream differs from CD-DA
+    pub fn cdda_mismatches(&self) -> Vec<CddaMismatch> {
+        let mut mismatches = Vec::new();
+        if self.sample_rate != 44100 {
+            mismatches.push(CddaMismatch::SampleRate(self.sample_rate));
+        }
+        if self.channels != 2 {
+            mismatches.push(CddaMismatch::Channels(self.channels));
+        }
+        if self.bits_per_sample != 16 {
+            mismatches.push(CddaMismatch::BitsPerSample(self.bits_per_sample));
+        }
+        if self.format != SampleFormat::Pcm {
+            mismatches.push(CddaMismatch::Format(self.format));
+        }
+        if self.sample_rate == 44100 && self.samples % SAMPLES_PER_FRAME != 0 {
+            mismatches.push(CddaMismatch::PartialFrame(self.samples % SAMPLES_PER_FRAME));
+        }
+        mismatches
+    }
+    pub fn is_cdda(&self) -> bool {
+        self.cdda_mismatches().is_empty()
+    }
+}
+impl fmt::Display for CddaMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SampleRate(rate) => write!(f, "sample rate is {} Hz instead of 44100 Hz", rate),
+            Self::Channels(channels) => write!(f, "{} channels instead of 2", channels),
+            Self::BitsPerSample(bits) => write!(f, "{}-bit samples instead of 16-bit", bits),
+            Self::Format(format) => write!(f, "{:?} samples instead of PCM", format),
+            Self::PartialFrame(samples) => {
+                write!(f, "{} samples after the last whole frame", samples)
+            }
+        }
+    }
+}
+
+/// Resolves the name in a `FILE` command against the cue sheet at `cue`
+///
+/// Absolute names are kept, and backslashes are treated as separators on every platform
+///
+/// ```rust
+/// use cuna::audio::resolve_path;
+/// use std::path::Path;
+///
+/// let path = resolve_path("rips/album/album.cue", r"CD1\01.wav");
+/// assert_eq!(path, Path::new("rips/album/CD1/01.wav"));
+/// ```
+pub fn resolve_path<P: AsRef<Path>>(cue: P, name: &str) -> PathBuf {
+    let dir = cue.as_ref().parent().unwrap_or_else(|| Path::new(""));
+    join_name(dir, name)
+}
+/// Joins the name in a `FILE` command to `dir`, see [`resolve_path()`]
+pub(crate) fn join_name(dir: &Path, name: &str) -> PathBuf {
+    if std::path::MAIN_SEPARATOR == '\\' {
+        dir.join(name)
+    } else {
+        dir.join(name.replace('\\', "/"))
+    }
+}
+
+/// Tells whether the samples of an uncompressed WAVE or AIFF stream in `range` are all digital silence
+pub fn is_silent<R: Read + Seek>(mut r: R, range: Range<TimeStamp>) -> io::Result<bool> {
+    let (info, data) = read_header(&mut r)?;
+    let data = data.ok_or_else(|| unsupported("samples of FLAC streams cannot be read"))?;
+    if !matches!(info.format, SampleFormat::Pcm | SampleFormat::Float) {
+        return Err(unsupported("samples of compressed streams cannot be read"));
+    }
+    let (start, end) = (
+        range.start.to_samples(info.sample_rate),
+        range.end.to_samples(info.sample_rate),
+    );
+    if end > info.samples {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "range is beyond the end of the stream",
+        ));
+    }
+    // 8-bit WAVE samples are unsigned
+    let silence = match (info.container, info.bits_per_sample) {
+        (Container::Wave, 1..=8) => 0x80,
+        _ => 0,
+    };
+    let block_align = info.channels as u64 * info.bits_per_sample.div_ceil(8) as u64;
+    let bytes = data + start * block_align..data + end.max(start) * block_align;
+    is_filled(&mut r, bytes, silence)
+}
+/// Tells whether the bytes in `range` all equal `fill`
+pub(crate) fn is_filled<R: Read + Seek>(
+    r: &mut R,
+    range: Range<u64>,
+    fill: u8,
+) -> io::Result<bool> {
+    r.seek(SeekFrom::Start(range.start))?;
+    let mut rest = range.end.saturating_sub(range.start);
+    let mut buf = vec![0; rest.min(1 << 16) as usize];
+    while rest > 0 {
+        let chunk = &mut buf[..rest.min(1 << 16) as usize];
+        r.read_exact(chunk)?;
+        if chunk.iter().any(|&b| b != fill) {
+            return Ok(false);
+        }
+        rest -= chunk.len() as u64;
+    }
+    Ok(true)
+}
+
+/// Reads the header of a stream and where its samples start, which is unknown for FLAC
+fn read_header<R: Read + Seek>(mut r: R) -> io::Result<(AudioInfo, Option<u64>)> {
+    let mut magic = [0; 4];
+    r.read_exact(&mut magic)?;
+    match &magic {
+        b"RIFF" => read_wave(r, false),
+        b"RF64" | b"BW64" => read_wave(r, true),
+        b"FORM" => read_aiff(r),
+        b"fLaC" => Ok((read_flac(r)?, None)),
+        _ => Err(invalid("unknown audio format")),
+    }
+}
+fn unsupported(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::Unsupported, msg)
+}
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+fn read_array<const N: usize>(r: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut buf = [0; N];
+    r.read_exact(&mut buf)?;
+    Ok(buf)
+}
+/// Walks through the chunks after the form type, calling `f` with each id and size
+///
+/// `f` returns how many bytes of the chunk it has read, or `None` to stop
+fn walk_chunks<R: Read + Seek>(
+    r: &mut R,
+    big_endian: bool,
+    mut f: impl FnMut(&mut R, [u8; 4], u64) -> io::Result<Option<u64>>,
+) -> io::Result<()> {
+    loop {
+        let id = match read_array::<4>(r) {
+            Ok(id) => id,
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+            Err(e) => return Err(e),
+        };
+        let size = read_array::<4>(r)?;
+        let size = if big_endian {
+            u32::from_be_bytes(size)
+        } else {
+            u32::from_le_bytes(size)
+        } as u64;
+        let read = match f(r, id, size)? {
+            Some(read) => read,
+            None => return Ok(()),
+        };
+        // chunks are padded to an even size
+        let skip = (size + (size & 1)).saturating_sub(read);
+        r.seek(SeekFrom::Current(skip as i64))?;
+    }
+}
+fn read_wave<R: Read + Seek>(mut r: R, rf64: bool) -> io::Result<(AudioInfo, Option<u64>)> {
+    let [_, _, _, _, w, a, v, e] = read_array::<8>(&mut r)?;
+    if &[w, a, v, e] != b"WAVE" {
+        return Err(invalid("not a WAVE file"));
+    }
+    let mut format = None;
+    let mut data = None;
+    let mut ds64 = None;
+    walk_chunks(&mut r, false, |r, id, size| match &id {
+        b"ds64" if rf64 => {
+            let chunk = read_array::<24>(r)?;
+            ds64 = Some(u64::from_le_bytes(chunk[8..16].try_into().unwrap()));
+            Ok(Some(24))
+        }
+        b"fmt " => {
+            let fmt = read_array::<16>(r)?;
+            let le16 = |i: usize| u16::from_le_bytes([fmt[i], fmt[i + 1]]);
+            let rate = u32::from_le_bytes([fmt[4], fmt[5], fmt[6], fmt[7]]);
+            let mut tag = le16(0);
+            let mut read = 16;
+            // WAVE_FORMAT_EXTENSIBLE stores the real tag at the start of the sub-format GUID
+            if tag == 0xfffe && size >= 40 {
+                let ext = read_array::<24>(r)?;
+                tag = u16::from_le_bytes([ext[8], ext[9]]);
+                read += 24;
+            }
+            format = Some((tag, le16(2), rate, le16(12), le16(14)));
+            Ok(Some(read))
+        }
+        b"data" => {
+            // RF64 writes `0xFFFFFFFF` here and the real size in `ds64`
+            let size = match ds64 {
+                Some(real) if size == u32::MAX as u64 => real,
+                _ => size,
+            };
+            data = Some((size, r.stream_position()?));
+            if format.is_some() {
+                return Ok(None);
+            }
+            r.seek(SeekFrom::Current((size + (size & 1)) as i64))?;
+            Ok(Some(u64::MAX))
+        }
+        _ => Ok(Some(0)),
+    })?;
+    let (tag, channels, sample_rate, block_align, bits_per_sample) =
+        format.ok_or_else(|| invalid("missing fmt chunk"))?;
+    let (data, offset) = data.ok_or_else(|| invalid("missing data chunk"))?;
+    if block_align == 0 {
+        return Err(invalid("invalid block align"));
+    }
+    let format = match tag {
+        1 => SampleFormat::Pcm,
+        3 => SampleFormat::Float,
+        tag => SampleFormat::Other(tag as u32),
+    };
+    let info = AudioInfo {
+        container: Container::Wave,
+        format,
+        sample_rate,
+        channels,
+        bits_per_sample,
+        samples: data / block_align as u64,
+    };
+    Ok((info, Some(offset)))
+}
+fn read_aiff<R: Read + Seek>(mut r: R) -> io::Result<(AudioInfo, Option<u64>)> {
+    let [_, _, _, _, a, i, f, c] = read_array::<8>(&mut r)?;
+    let compressed = match &[a, i, f, c] {
+        b"AIFF" => false,
+        b"AIFC" => true,
+        _ => return Err(invalid("not an AIFF file")),
+    };
+    let mut info = None;
+    let mut data = None;
+    walk_chunks(&mut r, true, |r, id, _| match &id {
+        b"COMM" => {
+            let comm = read_array::<18>(r)?;
+            let channels = u16::from_be_bytes([comm[0], comm[1]]);
+            let frames = u32::from_be_bytes([comm[2], comm[3], comm[4], comm[5]]);
+            let bits = u16::from_be_bytes([comm[6], comm[7]]);
+            let rate = extended_to_u32(comm[8..].try_into().unwrap());
+            let mut format = SampleFormat::Pcm;
+            if compressed {
+                let kind = read_array::<4>(r)?;
+                format = match &kind {
+                    b"NONE" | b"twos" | b"sowt" => SampleFormat::Pcm,
+                    b"fl32" | b"FL32" | b"fl64" | b"FL64" => SampleFormat::Float,
+                    _ => SampleFormat::Other(u32::from_be_bytes(kind)),
+                };
+            }
+            info = Some(AudioInfo {
+                container: Container::Aiff,
+                format,
+                sample_rate: rate,
+                channels,
+                bits_per_sample: bits,
+                samples: frames as u64,
+            });
+            Ok(data.is_none().then_some(18 + compressed as u64 * 4))
+        }
+        b"SSND" => {
+            let offset = u32::from_be_bytes(read_array::<4>(r)?);
+            read_array::<4>(r)?;
+            data = Some(r.stream_position()? + offset as u64);
+            Ok(info.is_none().then_some(8))
+        }
+        _ => Ok(Some(0)),
+    })?;
+    let info = info.ok_or_else(|| invalid("missing COMM chunk"))?;
+    Ok((info, data))
+}
+/// Converts an 80-bit IEEE 754 extended float to an integer, dropping the fraction
+fn extended_to_u32(bytes: [u8; 10]) -> u32 {
+    let exponent = u16::from_be_bytes([bytes[0], bytes[1]]) & 0x7fff;
+    let mantissa = u64::from_be_bytes(bytes[2..].try_into().unwrap());
+    let shift = 16383 + 63 - exponent as i32;
+    match shift {
+        0..=63 => u32::try_from(mantissa >> shift).unwrap_or(u32::MAX),
+        64.. => 0,
+        _ => u32::MAX,
+    }
+}
+fn read_flac<R: Read + Seek>(mut r: R) -> io::Result<AudioInfo> {
+    let [kind, ..] = read_array::<4>(&mut r)?;
+    // STREAMINFO must be the first metadata block
+    if kind & 0x7f != 0 {
+        return Err(invalid("missing STREAMINFO block"));
+    }
+    let info = read_array::<34>(&mut r)?;
+    let bits = u64::from_be_bytes(info[10..18].try_into().unwrap());
+    Ok(AudioInfo {
+        container: Container::Flac,
+        format: SampleFormat::Pcm,
+        sample_rate: (bits >> 44) as u32,
+        channels: ((bits >> 41) & 0x7) as u16 + 1,
+        bits_per_sample: ((bits >> 36) & 0x1f) as u16 + 1,
+        samples: bits & 0xf_ffff_ffff,
+    })
+}
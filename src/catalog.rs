@@ -0,0 +1,115 @@
+use crate::error::InvalidArgument;
+use std::fmt;
+use std::str::FromStr;
+
+/// A Media Catalog Number in the `CATALOG` command, which is a UPC-A or EAN-13 code
+///
+/// The digits are kept as written, so leading zeros are never lost.
+/// Cue sheets are parsed without checking the check digit unless
+/// [`ParseOptions::strict_catalog`](crate::parser::ParseOptions::strict_catalog) is set
+///
+/// ```rust
+/// use cuna::catalog::Catalog;
+///
+/// let catalog: Catalog = "0123456789012".parse().unwrap();
+/// assert_eq!(catalog.to_string(), "0123456789012");
+/// assert_eq!(catalog.to_upc_a().unwrap().as_str(), "123456789012");
+/// assert!("0123456789013".parse::<Catalog>().is_err()); // wrong check digit
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Catalog {
+    digits: [u8; 13],
+    len: u8,
+}
+
+impl Catalog {
+    /// Constructs a `Catalog` from 12 or 13 ASCII digits, accepting any check digit
+    pub fn without_check_digit(s: &str) -> Result<Self, InvalidArgument> {
+        if !matches!(s.len(), 12 | 13) || !s.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(InvalidArgument::InvalidCatalog);
+        }
+        let mut digits = [0; 13];
+        digits[..s.len()].copy_from_slice(s.as_bytes());
+        Ok(Self {
+            digits,
+            len: s.len() as u8,
+        })
+    }
+    /// Returns the digits as written
+    pub fn as_str(&self) -> &str {
+        // Only ASCII digits are stored
+        std::str::from_utf8(&self.digits[..self.len as usize]).unwrap()
+    }
+    /// Returns the value of the digits, which loses leading zeros
+    pub fn as_u64(&self) -> u64 {
+        self.as_str().parse().unwrap()
+    }
+    /// Returns the number of digits, which is 12 for UPC-A and 13 for EAN-13
+    #[allow(clippy::len_without_is_empty)]
+    pub const fn len(&self) -> usize {
+        self.len as usize
+    }
+    pub const fn is_upc_a(&self) -> bool {
+        self.len == 12
+    }
+    pub const fn is_ean13(&self) -> bool {
+        self.len == 13
+    }
+    /// Returns the last digit
+    pub fn check_digit(&self) -> u8 {
+        self.digits[self.len() - 1] - b'0'
+    }
+    /// Returns `true` if the check digit matches the GS1 checksum of the other digits
+    pub fn is_valid(&self) -> bool {
+        checksum(&self.digits[..self.len() - 1]) == self.check_digit()
+    }
+    /// Converts to EAN-13 by prepending a zero to a UPC-A code
+    pub fn to_ean13(&self) -> Self {
+        if self.is_ean13() {
+            return *self;
+        }
+        let mut digits = [b'0'; 13];
+        digits[1..].copy_from_slice(&self.digits[..12]);
+        Self { digits, len: 13 }
+    }
+    /// Converts to UPC-A, which is only possible for EAN-13 codes starting with zero
+    pub fn to_upc_a(&self) -> Option<Self> {
+        match (self.is_upc_a(), self.digits[0]) {
+            (true, _) => Some(*self),
+            (false, b'0') => {
+                let mut digits = [0; 13];
+                digits[..12].copy_from_slice(&self.digits[1..]);
+                Some(Self { digits, len: 12 })
+            }
+            _ => None,
+        }
+    }
+}
+impl FromStr for Catalog {
+    type Err = InvalidArgument;
+
+    /// Parses 12 or 13 digits and validates the check digit
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let catalog = Self::without_check_digit(s)?;
+        match catalog.is_valid() {
+            true => Ok(catalog),
+            false => Err(InvalidArgument::InvalidCatalog),
+        }
+    }
+}
+impl fmt::Display for Catalog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Computes the GS1 check digit, weighting digits by 3 and 1 alternately from the right
+fn checksum(digits: &[u8]) -> u8 {
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, d)| (d - b'0') as u32 * if i % 2 == 0 { 3 } else { 1 })
+        .sum();
+    ((10 - sum % 10) % 10) as u8
+}
@@ -1,9 +1,59 @@
+use crate::error::InvalidArgument;
 use crate::utils;
+use std::fmt;
 use std::iter::FromIterator;
 use std::ops::Index;
+use std::str::FromStr;
 
 #[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
 pub struct Comment(pub Vec<String>);
+/// A `REM` comment written by common rippers, parsed from its text
+///
+/// Comments which are not recognized, or whose values are malformed, are kept verbatim in `Other`
+///
+/// ```rust
+/// use cuna::comment::RemField;
+///
+/// assert_eq!(RemField::new("GENRE Pop"), RemField::Genre("Pop".to_owned()));
+/// assert_eq!(RemField::new("DATE 2011/11/30"), RemField::Date("2011/11/30".parse().unwrap()));
+/// assert_eq!(RemField::new("DISCID 59063607"), RemField::DiscId(0x59063607));
+/// assert_eq!(RemField::new(r#"COMMENT "ExactAudioCopy v0.95b3""#), RemField::Comment("ExactAudioCopy v0.95b3".to_owned()));
+/// assert_eq!(RemField::new("DISCNUMBER x"), RemField::Other("DISCNUMBER x".to_owned()));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum RemField {
+    Genre(String),
+    Date(RemDate),
+    /// The freedb disc ID, written in hex
+    DiscId(u32),
+    Comment(String),
+    Composer(String),
+    DiscNumber(u32),
+    TotalDiscs(u32),
+    /// The session of the following tracks on a multi-session disc, see [`Session`](crate::session::Session)
+    Session(u8),
+    Other(String),
+}
+/// The value of `REM DATE`, a year which may be followed by month and day like `2011/11/30`
+///
+/// It is kept as written, so the separators are not lost
+///
+/// ```rust
+/// use cuna::comment::RemDate;
+///
+/// let date: RemDate = "2011-11-30".parse().unwrap();
+/// assert_eq!((date.year(), date.month(), date.day()), (2011, Some(11), Some(30)));
+/// assert_eq!(date.to_string(), "2011-11-30");
+/// assert_eq!("2011".parse::<RemDate>().unwrap().month(), None);
+/// assert!("2011/13".parse::<RemDate>().is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RemDate {
+    text: String,
+    year: u16,
+    month: Option<u8>,
+    day: Option<u8>,
+}
 
 impl Comment {
     pub fn new(s: &str) -> Self {
@@ -14,6 +64,77 @@ impl Comment {
     pub fn push(&mut self, s: String) {
         self.0.push(s)
     }
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+    pub fn iter(&self) -> std::slice::Iter<'_, String> {
+        self.0.iter()
+    }
+    /// An iterator over the comments parsed as [`RemField`]s
+    pub fn fields(&self) -> impl Iterator<Item = RemField> + '_ {
+        self.0.iter().map(|s| RemField::new(s))
+    }
+    /// Returns the value of the first comment with the given key, ignoring ASCII case
+    ///
+    /// Surrounding quotes are removed
+    ///
+    /// ```rust
+    /// use cuna::comment::Comment;
+    ///
+    /// let comments = Comment::new("REM GENRE \"Anime\"\nREM REPLAYGAIN_ALBUM_GAIN -7.89 dB");
+    /// assert_eq!(comments.get("genre"), Some("Anime"));
+    /// assert_eq!(comments.get("REPLAYGAIN_ALBUM_GAIN"), Some("-7.89 dB"));
+    /// assert_eq!(comments.get("DATE"), None);
+    /// ```
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find_map(|s| split_key(s).filter(|(k, _)| k.eq_ignore_ascii_case(key)))
+            .map(|(_, value)| value)
+    }
+    /// Replaces the first comment with the same key as `field`, or appends it if there is none
+    pub fn set(&mut self, field: RemField) -> Option<RemField> {
+        let key = field.key().to_owned();
+        let found = self.0.iter_mut().find(|s| match split_key(s) {
+            Some((k, _)) => k.eq_ignore_ascii_case(&key),
+            None => false,
+        });
+        match found {
+            Some(s) => Some(RemField::new(&std::mem::replace(s, field.to_string()))),
+            None => {
+                self.0.push(field.to_string());
+                None
+            }
+        }
+    }
+    pub fn genre(&self) -> Option<&str> {
+        self.get("GENRE")
+    }
+    pub fn date(&self) -> Option<RemDate> {
+        self.get("DATE").and_then(|date| date.parse().ok())
+    }
+    pub fn disc_id(&self) -> Option<u32> {
+        self.get("DISCID")
+            .and_then(|id| u32::from_str_radix(id, 16).ok())
+    }
+    pub fn comment(&self) -> Option<&str> {
+        self.get("COMMENT")
+    }
+    pub fn composer(&self) -> Option<&str> {
+        self.get("COMPOSER")
+    }
+    pub fn disc_number(&self) -> Option<u32> {
+        self.get("DISCNUMBER").and_then(|n| n.parse().ok())
+    }
+    pub fn total_discs(&self) -> Option<u32> {
+        self.get("TOTALDISCS").and_then(|n| n.parse().ok())
+    }
+    pub fn session(&self) -> Option<u8> {
+        self.get("SESSION").and_then(|n| n.parse().ok())
+    }
 }
 impl<S: Into<String>> FromIterator<S> for Comment {
     fn from_iter<T: IntoIterator<Item = S>>(iter: T) -> Self {
@@ -28,6 +149,14 @@ impl IntoIterator for Comment {
         self.0.into_iter()
     }
 }
+impl<'a> IntoIterator for &'a Comment {
+    type Item = &'a String;
+    type IntoIter = std::slice::Iter<'a, String>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
 impl Index<usize> for Comment {
     type Output = String;
 
@@ -36,3 +165,120 @@ impl Index<usize> for Comment {
         &self.0[index]
     }
 }
+impl RemField {
+    /// Parses the text of a `REM` comment, without the leading `REM`
+    pub fn new(s: &str) -> Self {
+        let (key, value) = match split_key(s) {
+            Some(kv) => kv,
+            None => return Self::Other(s.to_owned()),
+        };
+        let field = match key.to_ascii_uppercase().as_str() {
+            "GENRE" => Some(Self::Genre(value.to_owned())),
+            "DATE" => value.parse().ok().map(Self::Date),
+            "DISCID" => u32::from_str_radix(value, 16).ok().map(Self::DiscId),
+            "COMMENT" => Some(Self::Comment(value.to_owned())),
+            "COMPOSER" => Some(Self::Composer(value.to_owned())),
+            "DISCNUMBER" => value.parse().ok().map(Self::DiscNumber),
+            "TOTALDISCS" => value.parse().ok().map(Self::TotalDiscs),
+            "SESSION" => value.parse().ok().map(Self::Session),
+            _ => None,
+        };
+        field.unwrap_or_else(|| Self::Other(s.to_owned()))
+    }
+    /// Returns the key of the comment in upper case, or the whole comment for `Other`
+    pub fn key(&self) -> &str {
+        match self {
+            Self::Genre(_) => "GENRE",
+            Self::Date(_) => "DATE",
+            Self::DiscId(_) => "DISCID",
+            Self::Comment(_) => "COMMENT",
+            Self::Composer(_) => "COMPOSER",
+            Self::DiscNumber(_) => "DISCNUMBER",
+            Self::TotalDiscs(_) => "TOTALDISCS",
+            Self::Session(_) => "SESSION",
+            Self::Other(s) => split_key(s).map_or(s.as_str(), |(key, _)| key),
+        }
+    }
+}
+impl RemDate {
+    pub fn year(&self) -> u16 {
+        self.year
+    }
+    pub fn month(&self) -> Option<u8> {
+        self.month
+    }
+    pub fn day(&self) -> Option<u8> {
+        self.day
+    }
+    /// Returns the date as written
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+}
+impl FromStr for RemDate {
+    type Err = InvalidArgument;
+
+    /// Parses dates like `2011`, `2011-11`, `2011-11-30`, `2011/11/30` or `2011.11.30`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split(['-', '/', '.']);
+        let mut next = |len, min, max| {
+            parts
+                .next()
+                .map(|part| parse_part(part, len, min, max).ok_or(InvalidArgument::InvalidDate))
+                .transpose()
+        };
+        let year = next(4, 0, 9999)?.ok_or(InvalidArgument::InvalidDate)?;
+        let month = next(2, 1, 12)?.map(|month| month as u8);
+        let day = next(2, 1, 31)?.map(|day| day as u8);
+        if next(0, 0, 0)?.is_some() || (day.is_some() && month.is_none()) {
+            return Err(InvalidArgument::InvalidDate);
+        }
+        Ok(Self {
+            text: s.to_owned(),
+            year,
+            month,
+            day,
+        })
+    }
+}
+impl fmt::Display for RemDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+impl fmt::Display for RemField {
+    /// Writes the comment as it appears after `REM`, quoting values with spaces
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = |f: &mut fmt::Formatter<'_>, s: &str| match s.contains(' ') {
+            true => write!(f, r#"{} "{}""#, self.key(), s),
+            false => write!(f, "{} {}", self.key(), s),
+        };
+        match self {
+            Self::Genre(s) | Self::Comment(s) | Self::Composer(s) => text(f, s),
+            Self::Date(date) => write!(f, "DATE {}", date),
+            Self::DiscId(id) => write!(f, "DISCID {:08X}", id),
+            Self::DiscNumber(n) => write!(f, "DISCNUMBER {}", n),
+            Self::TotalDiscs(n) => write!(f, "TOTALDISCS {}", n),
+            Self::Session(n) => write!(f, "SESSION {:02}", n),
+            Self::Other(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+/// Splits a comment into key and unquoted value
+fn split_key(s: &str) -> Option<(&str, &str)> {
+    let (key, value) = s.trim().split_once(char::is_whitespace)?;
+    let value = value.trim();
+    let value = value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .unwrap_or(value);
+    Some((key, value))
+}
+/// Parses a number of `len` digits in `min..=max`
+fn parse_part(s: &str, len: usize, min: u16, max: u16) -> Option<u16> {
+    if s.len() != len || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    s.parse().ok().filter(|n| (min..=max).contains(n))
+}
@@ -1,12 +1,27 @@
+use crate::catalog::Catalog;
 use crate::comment::Comment;
+use crate::comment::RemDate;
+use crate::encoding;
+use crate::encoding::Detection;
+use crate::encoding::TextEncoding;
+use crate::error::Diagnostic;
+use crate::error::DurationError;
 use crate::error::Error;
+use crate::error::LayoutError;
 use crate::header::Header;
+use crate::layout::DiscLayout;
+use crate::length::FileLengthProvider;
+use crate::parser;
 use crate::parser::Command;
 use crate::parser::Parna;
+use crate::session::Session;
+use crate::time::TimeStamp;
 use crate::track::Track;
 use crate::track::Disc;
 use crate::trim_utf8_header;
+use std::fmt;
 use std::fs::File;
+use std::io;
 use std::io::BufRead;
 use std::io::BufReader;
 use std::io::Cursor;
@@ -29,6 +44,9 @@ use std::str::FromStr;
 pub struct Cuna {
     pub header: Header,
     pub files: Vec<Disc>,
+    /// `REM` comments before the first `FILE`
+    ///
+    /// Those inside `FILE` or `TRACK` scopes are stored in [`Disc`] and [`Track`]
     pub comments: Comment,
 }
 
@@ -49,6 +67,24 @@ impl Cuna {
         // Never panics since `Cursor::fill_buf()` always returns `Ok()`
         Self::from_buf_read_suc(cursor).unwrap()
     }
+    /// Parses an str as cue sheet like [`new_suc()`](Self::new_suc),
+    /// and returns every problem found along with the best-effort result
+    ///
+    /// ```rust
+    /// use cuna::Cuna;
+    /// use cuna::error::Severity;
+    ///
+    /// let (sheet, diagnostics) = Cuna::new_with_diagnostics("TITLE \"Hi\"\nCATALOG 42\nFOO bar");
+    /// assert_eq!(sheet.title(), &["Hi".to_owned()]);
+    /// assert_eq!(diagnostics.len(), 2);
+    /// assert_eq!((diagnostics[0].line(), diagnostics[0].severity()), (2, Severity::Error));
+    /// assert_eq!((diagnostics[1].line(), diagnostics[1].severity()), (3, Severity::Warning));
+    /// ```
+    pub fn new_with_diagnostics(s: &str) -> (Self, Vec<Diagnostic>) {
+        let mut sheet = Self::default();
+        let diagnostics = Parna::new(trim_utf8_header(s)).parse_with_diagnostics(&mut sheet);
+        (sheet, diagnostics)
+    }
     pub const fn with_parts(header: Header, files: Vec<Disc>, comments: Comment) -> Self {
         Self {
             header,
@@ -94,6 +130,46 @@ impl Cuna {
         let file = File::open(path)?;
         Self::from_file(&file)
     }
+    /// Opens a file, decodes it with `encoding` and parses it as a cue sheet
+    ///
+    /// If `encoding` is `None`, it is detected like [`from_bytes()`](Self::from_bytes)
+    ///
+    /// Returns the encoding used to decode the file along with the cue sheet
+    pub fn open_with_encoding<P: AsRef<Path>>(
+        path: P,
+        encoding: Option<TextEncoding>,
+    ) -> Result<(Self, TextEncoding), Error> {
+        let bytes = std::fs::read(path)?;
+        match encoding {
+            Some(encoding) => Ok((Self::from_bytes_with_encoding(&bytes, encoding)?, encoding)),
+            None => Self::from_bytes(&bytes).map(|(sheet, detected)| (sheet, detected.encoding)),
+        }
+    }
+    /// Detects the encoding of `bytes` with [`encoding::detect()`] and parses it as a cue sheet
+    ///
+    /// Returns the detection result along with the cue sheet
+    ///
+    /// ```rust
+    /// use cuna::Cuna;
+    /// use cuna::encoding::TextEncoding;
+    ///
+    /// // `TITLE "ギルティクラウン"` in Shift-JIS
+    /// let bytes = b"TITLE \"\x83\x4d\x83\x8b\x83\x65\x83\x42\x83\x4e\x83\x89\x83\x45\x83\x93\"";
+    /// let (cue, detected) = Cuna::from_bytes(bytes).unwrap();
+    /// assert_eq!(detected.encoding, TextEncoding::ShiftJis);
+    /// assert_eq!(cue.title(), &["ギルティクラウン".to_owned()]);
+    /// ```
+    pub fn from_bytes(bytes: &[u8]) -> Result<(Self, Detection), Error> {
+        let detected = encoding::detect(bytes);
+        let sheet = Self::from_bytes_with_encoding(bytes, detected.encoding)?;
+        Ok((sheet, detected))
+    }
+    /// Decodes `bytes` with `encoding` and parses it as a cue sheet
+    ///
+    /// Fails if `bytes` is malformed in `encoding`
+    pub fn from_bytes_with_encoding(bytes: &[u8], encoding: TextEncoding) -> Result<Self, Error> {
+        encoding.decode(bytes)?.parse()
+    }
     /// Opens a file and parses it as a cue sheet like [`open()`](Self::open()),
     /// except this method skips bad lines.
     ///
@@ -127,21 +203,32 @@ impl Cuna {
     /// except this method skips bad lines.
     ///
     /// **Only supports UTF-8 encoding (with BOM or not)**
-    pub fn from_buf_read_suc(mut buf: impl BufRead) -> std::io::Result<Self> {
+    pub fn from_buf_read_suc(buf: impl BufRead) -> std::io::Result<Self> {
+        Self::from_buf_read_with_diagnostics(buf).map(|(sheet, _)| sheet)
+    }
+    /// Reads `buf` and parses it as a cue sheet like [`new_with_diagnostics()`](Self::new_with_diagnostics()),
+    /// only stops if fails to read from `buf`
+    ///
+    /// **Only supports UTF-8 encoding (with BOM or not)**
+    pub fn from_buf_read_with_diagnostics(
+        mut buf: impl BufRead,
+    ) -> std::io::Result<(Self, Vec<Diagnostic>)> {
         let mut sheet = Self::default();
+        let mut diagnostics = Vec::new();
         let mut buffer = String::new();
+        let mut at = 1;
         loop {
             match buf.read_line(&mut buffer) {
-                Ok(0) => break Ok(sheet),
-                Ok(_) => {
-                    let command = match Command::new(trim_utf8_header(&buffer)) {
-                        Ok(c) => c,
-                        _ => continue,
-                    };
-                    let _ = command.parse(&mut sheet);
-                }
+                Ok(0) => break Ok((sheet, diagnostics)),
+                Ok(_) => diagnostics.extend(parser::parse_line(
+                    trim_utf8_header(&buffer),
+                    at,
+                    Default::default(),
+                    &mut sheet,
+                )),
                 Err(e) => break Err(e),
             }
+            at += 1;
             buffer.clear();
         }
     }
@@ -157,9 +244,48 @@ impl Cuna {
     pub fn songwriter(&self) -> &Vec<String> {
         self.header.songwriter()
     }
-    pub fn catalog(&self) -> Option<u64> {
+    pub fn catalog(&self) -> Option<Catalog> {
         self.header.catalog()
     }
+    /// Returns the value of `REM GENRE`
+    ///
+    /// ```rust
+    /// use cuna::Cuna;
+    ///
+    /// let cue = Cuna::open("tests/EGOIST - Departures ～あなたにおくるアイの歌～.cue").unwrap();
+    /// assert_eq!(cue.genre(), Some("Pop"));
+    /// assert_eq!(cue.date().unwrap().year(), 2011);
+    /// assert_eq!(cue.disc_id(), Some(0x59063607));
+    /// assert_eq!(cue.comment(), Some("ExactAudioCopy v0.99pb5"));
+    /// assert_eq!(cue.composer(), None);
+    /// ```
+    pub fn genre(&self) -> Option<&str> {
+        self.comments.genre()
+    }
+    /// Returns the value of `REM DATE`
+    pub fn date(&self) -> Option<RemDate> {
+        self.comments.date()
+    }
+    /// Returns the value of `REM DISCID`
+    pub fn disc_id(&self) -> Option<u32> {
+        self.comments.disc_id()
+    }
+    /// Returns the value of `REM COMMENT`
+    pub fn comment(&self) -> Option<&str> {
+        self.comments.comment()
+    }
+    /// Returns the value of `REM COMPOSER`
+    pub fn composer(&self) -> Option<&str> {
+        self.comments.composer()
+    }
+    /// Returns the value of `REM DISCNUMBER`
+    pub fn disc_number(&self) -> Option<u32> {
+        self.comments.disc_number()
+    }
+    /// Returns the value of `REM TOTALDISCS`
+    pub fn total_discs(&self) -> Option<u32> {
+        self.comments.total_discs()
+    }
     pub fn files(&self) -> &Vec<Disc> {
         &self.files
     }
@@ -197,9 +323,113 @@ impl Cuna {
         self.last_file_mut().and_then(Disc::last_track_mut)
     }
     /// An iterator over the `TRACK`s in all the `FILE`s
-    pub fn tracks(&self) -> Flatten<Iter<Disc>> {
+    pub fn tracks(&self) -> Flatten<Iter<'_, Disc>> {
         self.files.iter().flatten()
     }
+    /// Groups the tracks by session, with every track in session 1 if there is no `REM SESSION`
+    ///
+    /// ```rust
+    /// use cuna::Cuna;
+    ///
+    /// let sheet = Cuna::new(r#"FILE "Range.wav" WAVE
+    ///   TRACK 01 AUDIO
+    ///     INDEX 01 00:00:00
+    ///   TRACK 02 AUDIO
+    ///     INDEX 01 04:00:00
+    /// REM SESSION 02
+    /// FILE "Data.bin" BINARY
+    ///   TRACK 03 MODE1/2352
+    ///     INDEX 01 00:00:00"#).unwrap();
+    /// let sessions = sheet.sessions();
+    /// assert_eq!(sessions.len(), 2);
+    /// assert_eq!(sessions[0].tracks.len(), 2);
+    /// assert_eq!(sessions[1].number, 2);
+    /// assert_eq!(sessions[1].tracks[0].id(), 3);
+    /// ```
+    pub fn sessions(&self) -> Vec<Session<'_>> {
+        let mut sessions: Vec<Session> = Vec::new();
+        for (track, number) in self.tracks().zip(self.track_sessions()) {
+            match sessions.last_mut() {
+                Some(session) if session.number == number => session.tracks.push(track),
+                _ => sessions.push(Session {
+                    number,
+                    tracks: vec![track],
+                }),
+            }
+        }
+        sessions
+    }
+    /// Returns the session of each track in order
+    pub(crate) fn track_sessions(&self) -> Vec<u8> {
+        let mut current = 1;
+        let mut next = self.comments.session();
+        let mut sessions = Vec::new();
+        for disc in &self.files {
+            next = disc.comments.session().or(next);
+            for track in disc.tracks() {
+                current = next.take().unwrap_or(current);
+                sessions.push(current);
+                next = track.comments.session();
+            }
+        }
+        sessions
+    }
+    /// Computes the position of every track on the disc, see [`DiscLayout::new()`]
+    pub fn layout(&self, lengths: &[TimeStamp]) -> Result<DiscLayout, LayoutError> {
+        DiscLayout::new(self, lengths)
+    }
+    /// Asks `provider` for the length of every `FILE`
+    pub fn file_lengths<P>(&self, provider: &P) -> io::Result<Vec<TimeStamp>>
+    where
+        P: FileLengthProvider + ?Sized,
+    {
+        self.files
+            .iter()
+            .enumerate()
+            .map(|(index, file)| provider.file_length(index, file))
+            .collect()
+    }
+    /// Returns the length of every track in order, including the last track of each `FILE`
+    ///
+    /// See [`TrackLayout::length`](crate::layout::TrackLayout::length) for where a track ends
+    pub fn track_durations<P>(&self, provider: &P) -> Result<Vec<TimeStamp>, DurationError>
+    where
+        P: FileLengthProvider + ?Sized,
+    {
+        let lengths = self.file_lengths(provider)?;
+        let layout = self.layout(&lengths)?;
+        Ok(layout.tracks.iter().map(|track| track.length).collect())
+    }
+    /// Writes the cue sheet to `w`, see [`Display`](#impl-Display-for-Cuna) for the layout
+    ///
+    /// ```rust
+    /// use cuna::Cuna;
+    ///
+    /// let cue = Cuna::new("REM DATE 2011\nTITLE \"Departures\"").unwrap();
+    /// let mut buf = Vec::new();
+    /// cue.write_to(&mut buf).unwrap();
+    /// assert_eq!(buf, b"REM DATE 2011\nTITLE \"Departures\"\n");
+    /// ```
+    pub fn write_to(&self, mut w: impl io::Write) -> io::Result<()> {
+        write!(w, "{}", self)
+    }
+}
+impl fmt::Display for Cuna {
+    /// Writes the cue sheet in the standard CDRWIN layout
+    ///
+    /// Comments come first, followed by the disc-level commands and the `FILE`s.
+    /// `TRACK`s are indented by two spaces and their commands by four.
+    /// Parsing the output again gives back an equal `Cuna`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for comment in self.comments.0.iter() {
+            writeln!(f, "{}", Command::Rem(comment))?;
+        }
+        write!(f, "{}", self.header)?;
+        for file in self.files() {
+            write!(f, "{}", file)?;
+        }
+        Ok(())
+    }
 }
 impl FromStr for Cuna {
     type Err = Error;
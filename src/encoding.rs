@@ -0,0 +1,257 @@
+//! Decoding cue sheets written in legacy encodings
+//!
+//! Besides UTF-8, rippers often write cue sheets in the system code page
+//! (Shift-JIS, GBK, Big5 or Windows-1252) or in UTF-16.
+//! [`detect()`] guesses the encoding from the BOM and the byte patterns,
+//! while [`TextEncoding::decode()`] decodes with a known encoding.
+//!
+//! Constructors of [`Cuna`](crate::Cuna) reading files or buffers, like [`Cuna::open()`](crate::Cuna::open),
+//! only support UTF-8. Use [`Cuna::open_with_encoding()`](crate::Cuna::open_with_encoding)
+//! or [`Cuna::from_bytes()`](crate::Cuna::from_bytes) for other encodings.
+use crate::error::ParseError;
+use encoding_rs::Encoding;
+use std::borrow::Cow;
+use std::fmt;
+
+/// Encodings supported when reading a cue sheet from bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TextEncoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    ShiftJis,
+    Gbk,
+    Big5,
+    Windows1252,
+}
+/// The result of [`detect()`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Detection {
+    pub encoding: TextEncoding,
+    /// How likely the guess is correct, between 0 and 1
+    ///
+    /// It is always 1 if a BOM is found or the input is pure ASCII
+    pub confidence: f32,
+    /// Whether the input starts with a BOM
+    pub bom: bool,
+}
+
+impl TextEncoding {
+    /// Legacy encodings tried when the input is not valid UTF-8
+    const LEGACY: [Self; 4] = [Self::ShiftJis, Self::Gbk, Self::Big5, Self::Windows1252];
+
+    /// Returns the WHATWG name of the encoding
+    pub fn name(self) -> &'static str {
+        self.encoding().name()
+    }
+    /// Returns the byte order mark of the encoding, which is empty for legacy encodings
+    pub const fn bom(self) -> &'static [u8] {
+        match self {
+            Self::Utf8 => b"\xEF\xBB\xBF",
+            Self::Utf16Le => b"\xFF\xFE",
+            Self::Utf16Be => b"\xFE\xFF",
+            _ => b"",
+        }
+    }
+    /// Decodes `bytes`, stripping the BOM of this encoding if any
+    ///
+    /// Fails if `bytes` contains sequences which are malformed in this encoding
+    ///
+    /// ```rust
+    /// use cuna::encoding::TextEncoding;
+    ///
+    /// let bytes = b"TITLE \"\x83\x65\x83\x58\x83\x67\"";
+    /// assert_eq!(TextEncoding::ShiftJis.decode(bytes).unwrap(), "TITLE \"テスト\"");
+    /// assert!(TextEncoding::Utf8.decode(bytes).is_err());
+    /// ```
+    pub fn decode(self, bytes: &[u8]) -> Result<Cow<'_, str>, ParseError> {
+        let bytes = bytes.strip_prefix(self.bom()).unwrap_or(bytes);
+        self.encoding()
+            .decode_without_bom_handling_and_without_replacement(bytes)
+            .ok_or(ParseError::DecodeError(self))
+    }
+    /// Like [`decode()`](Self::decode), but replaces malformed sequences with U+FFFD
+    pub fn decode_lossy(self, bytes: &[u8]) -> Cow<'_, str> {
+        let bytes = bytes.strip_prefix(self.bom()).unwrap_or(bytes);
+        self.encoding().decode_without_bom_handling(bytes).0
+    }
+    fn encoding(self) -> &'static Encoding {
+        match self {
+            Self::Utf8 => encoding_rs::UTF_8,
+            Self::Utf16Le => encoding_rs::UTF_16LE,
+            Self::Utf16Be => encoding_rs::UTF_16BE,
+            Self::ShiftJis => encoding_rs::SHIFT_JIS,
+            Self::Gbk => encoding_rs::GBK,
+            Self::Big5 => encoding_rs::BIG5,
+            Self::Windows1252 => encoding_rs::WINDOWS_1252,
+        }
+    }
+}
+impl fmt::Display for TextEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+impl Detection {
+    const fn certain(encoding: TextEncoding, bom: bool) -> Self {
+        Self {
+            encoding,
+            confidence: 1.0,
+            bom,
+        }
+    }
+}
+
+/// Guesses the encoding of `bytes`
+///
+/// A BOM is always trusted. Otherwise UTF-16 is recognized by its zero bytes,
+/// valid UTF-8 is preferred, and the legacy encodings are scored
+/// by how plausible the decoded characters are.
+///
+/// ```rust
+/// use cuna::encoding::{detect, TextEncoding};
+///
+/// // "ギルティクラウン" in Shift-JIS
+/// let sjis = b"\x83\x4d\x83\x8b\x83\x65\x83\x42\x83\x4e\x83\x89\x83\x45\x83\x93";
+/// assert_eq!(detect(sjis).encoding, TextEncoding::ShiftJis);
+/// assert_eq!(detect(b"\xFF\xFEA\0").encoding, TextEncoding::Utf16Le);
+/// assert_eq!(detect("Pok\u{e9}mon".as_bytes()).encoding, TextEncoding::Utf8);
+/// assert_eq!(detect(b"Pok\xE9mon").encoding, TextEncoding::Windows1252);
+/// ```
+pub fn detect(bytes: &[u8]) -> Detection {
+    for encoding in [
+        TextEncoding::Utf8,
+        TextEncoding::Utf16Le,
+        TextEncoding::Utf16Be,
+    ] {
+        if bytes.starts_with(encoding.bom()) {
+            return Detection::certain(encoding, true);
+        }
+    }
+    if let Some(encoding) = detect_utf16(bytes) {
+        return Detection {
+            encoding,
+            confidence: 0.9,
+            bom: false,
+        };
+    }
+    if bytes.is_ascii() {
+        return Detection::certain(TextEncoding::Utf8, false);
+    }
+    if std::str::from_utf8(bytes).is_ok() {
+        // Multi-byte sequences of legacy encodings rarely happen to be valid UTF-8
+        return Detection {
+            encoding: TextEncoding::Utf8,
+            confidence: 0.99,
+            bom: false,
+        };
+    }
+    TextEncoding::LEGACY
+        .iter()
+        .filter_map(|&encoding| {
+            let text = encoding.decode(bytes).ok()?;
+            Some(Detection {
+                encoding,
+                confidence: score(encoding, &text, bytes),
+                bom: false,
+            })
+        })
+        // `max_by` returns the last maximum, so iterate in reverse to prefer the earlier one
+        .rev()
+        .max_by(|a, b| a.confidence.total_cmp(&b.confidence))
+        .unwrap_or(Detection {
+            encoding: TextEncoding::Windows1252,
+            confidence: 0.0,
+            bom: false,
+        })
+}
+
+/// UTF-16 text in cue sheets has many ASCII commands, so many high bytes are zero,
+/// while the low bytes are rarely zero even in CJK text
+fn detect_utf16(bytes: &[u8]) -> Option<TextEncoding> {
+    if bytes.len() < 2 || bytes.len() % 2 != 0 {
+        return None;
+    }
+    let pairs = bytes.len() / 2;
+    let zeros_at = |offset: usize| {
+        bytes
+            .iter()
+            .skip(offset)
+            .step_by(2)
+            .filter(|&&b| b == 0)
+            .count()
+    };
+    let (even, odd) = (zeros_at(0), zeros_at(1));
+    if odd * 4 >= pairs && odd > even * 4 {
+        Some(TextEncoding::Utf16Le)
+    } else if even * 4 >= pairs && even > odd * 4 {
+        Some(TextEncoding::Utf16Be)
+    } else {
+        None
+    }
+}
+fn score(encoding: TextEncoding, text: &str, bytes: &[u8]) -> f32 {
+    let chars: Vec<_> = text.chars().filter(|c| !c.is_ascii()).collect();
+    if chars.is_empty() {
+        return 0.0;
+    }
+    let good: f32 = chars
+        .iter()
+        .map(|&c| match (encoding, c as u32) {
+            // Hiragana and katakana
+            (TextEncoding::ShiftJis, 0x3040..=0x30FF) => 1.0,
+            (_, 0x3040..=0x30FF) => 0.5,
+            // CJK ideographs, symbols and fullwidth forms
+            (TextEncoding::Windows1252, _) => latin(c),
+            (_, 0x4E00..=0x9FFF | 0x3000..=0x303F | 0xFF01..=0xFF5E) => 1.0,
+            _ => 0.0,
+        })
+        .sum();
+    let ratio = good / chars.len() as f32;
+    match encoding {
+        TextEncoding::Windows1252 => ratio,
+        _ => {
+            let (low_trails, latin_words) = byte_stats(bytes);
+            let multi_byte = ratio * (1.0 - latin_words);
+            match encoding {
+                // Trail bytes of GB2312 characters are always above 0xA0,
+                // while about half of those in Big5 and Shift-JIS are not
+                TextEncoding::Gbk => multi_byte * (1.0 - low_trails),
+                TextEncoding::Big5 => multi_byte * (0.5 + low_trails).min(1.0),
+                _ => multi_byte,
+            }
+        }
+    }
+}
+/// Letters and punctuations commonly seen in Western European text
+fn latin(c: char) -> f32 {
+    match c as u32 {
+        0xC0..=0xFF | 0x2018..=0x201E | 0x2013 | 0x2014 | 0x2026 => 1.0,
+        _ => 0.0,
+    }
+}
+/// Returns the ratio of double-byte pairs with a trail byte below 0xA1,
+/// and the ratio of non-ASCII bytes sitting between two ASCII letters
+fn byte_stats(bytes: &[u8]) -> (f32, f32) {
+    let (mut pairs, mut low_trails) = (0, 0);
+    let mut iter = bytes.iter().peekable();
+    while let Some(&b) = iter.next() {
+        if b >= 0x81 {
+            if let Some(&&trail) = iter.peek() {
+                pairs += 1;
+                low_trails += (trail < 0xA1) as usize;
+                iter.next();
+            }
+        }
+    }
+    let high = bytes.iter().filter(|b| !b.is_ascii()).count();
+    let latin_words = bytes
+        .windows(3)
+        .filter(|w| w[0].is_ascii_alphabetic() && !w[1].is_ascii() && w[2].is_ascii_alphabetic())
+        .count();
+    let ratio = |n: usize, total: usize| match total {
+        0 => 0.0,
+        _ => n as f32 / total as f32,
+    };
+    (ratio(low_trails, pairs), ratio(latin_words, high))
+}
@@ -1,3 +1,4 @@
+use crate::encoding::TextEncoding;
 use std::fmt;
 use std::io;
 use std::mem::discriminant;
@@ -12,6 +13,69 @@ pub enum InvalidArgument {
     MissingArgument,
     #[error("Invalid id")]
     InvalidId,
+    #[error("Invalid flag")]
+    InvalidFlag,
+    #[error("Invalid catalog")]
+    InvalidCatalog,
+    #[error("Invalid ISRC")]
+    InvalidIsrc,
+    #[error("Invalid date")]
+    InvalidDate,
+}
+/// A time value does not fit in the target type
+#[derive(Debug, Error, PartialEq, Eq, Hash, Copy, Clone)]
+#[error("Time value out of range")]
+pub struct TimeOutOfRange;
+/// Reasons why a [`DiscLayout`](crate::layout::DiscLayout) or an [`ImageLayout`](crate::image::ImageLayout) cannot be built
+#[derive(Debug, Error, PartialEq, Eq, Hash, Copy, Clone)]
+pub enum LayoutError {
+    #[error("Expected {expected} file lengths, found {found}")]
+    FileCount { expected: usize, found: usize },
+    #[error("Track {0} has no INDEX 01")]
+    MissingIndex(u8),
+    #[error("Track {0} has an index beyond the end of its file")]
+    IndexOutOfFile(u8),
+    #[error("Track {0} has an index before the previous one")]
+    Unordered(u8),
+    #[error("Track {0} has no known sector size")]
+    UnknownSectorSize(u8),
+    #[error("Track {0} ends with a partial sector")]
+    PartialSector(u8),
+    #[error("File {0} does not hold exactly one track")]
+    TrackPerFile(usize),
+    #[error(transparent)]
+    OutOfRange(#[from] TimeOutOfRange),
+}
+/// Reasons why the durations of tracks cannot be computed
+#[derive(Debug, Error)]
+pub enum DurationError {
+    /// Fails to get the length of a file
+    #[error("IoError: {0}")]
+    IoError(#[from] io::Error),
+    #[error(transparent)]
+    Layout(#[from] LayoutError),
+}
+/// Reasons why an [`ImageLayout`](crate::image::ImageLayout) cannot be read from the image files
+#[derive(Debug, Error)]
+pub enum ImageError {
+    /// Fails to get the size of a file
+    #[error("IoError: {0}")]
+    IoError(#[from] io::Error),
+    #[error(transparent)]
+    Layout(#[from] LayoutError),
+}
+/// Reasons why a gap cannot be moved
+#[derive(Debug, Error)]
+pub enum GapError {
+    /// Fails to read a file
+    #[error("IoError: {0}")]
+    IoError(#[from] io::Error),
+    #[error("Track {0} does not exist")]
+    NoSuchTrack(u8),
+    #[error("Track {0} is shorter than the gap")]
+    TooLong(u8),
+    #[error(transparent)]
+    OutOfRange(#[from] TimeOutOfRange),
 }
 #[derive(Debug, Error)]
 pub enum ParseError {
@@ -25,6 +89,9 @@ pub enum ParseError {
     /// Fails to read a file
     #[error("IoError: {0}")]
     IoError(#[from] io::Error),
+    /// The input is malformed in the given encoding
+    #[error("DecodeError: invalid {0} sequence")]
+    DecodeError(TextEncoding),
 }
 #[derive(Debug, Error)]
 pub struct Error {
@@ -92,3 +159,58 @@ impl PartialEq for Error {
         self.kind() == other.kind()
     }
 }
+
+/// How serious a [`Diagnostic`] is
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone, PartialOrd, Ord)]
+pub enum Severity {
+    /// The line was not understood and has been skipped, e.g. an unknown command
+    Warning,
+    /// The line is invalid and has been skipped
+    Error,
+}
+/// A problem found in a line while parsing in the collecting mode,
+/// see [`Cuna::new_with_diagnostics()`](crate::Cuna::new_with_diagnostics)
+#[derive(Debug, Error, PartialEq)]
+#[error("{severity}: {error}")]
+pub struct Diagnostic {
+    severity: Severity,
+    #[source]
+    error: Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Warning => write!(formatter, "warning"),
+            Self::Error => write!(formatter, "error"),
+        }
+    }
+}
+impl Diagnostic {
+    pub const fn new(severity: Severity, error: ParseError, at: usize) -> Self {
+        Self {
+            severity,
+            error: Error::new(error, at),
+        }
+    }
+    pub const fn severity(&self) -> Severity {
+        self.severity
+    }
+    /// Returns the line number, starting from 1
+    pub fn line(&self) -> usize {
+        // Always constructed with a line number
+        self.error.pos().unwrap_or_default()
+    }
+    pub const fn kind(&self) -> &ParseError {
+        self.error.kind()
+    }
+    pub const fn error(&self) -> &Error {
+        &self.error
+    }
+    pub fn into_error(self) -> Error {
+        self.error
+    }
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
@@ -0,0 +1,135 @@
+use std::convert::Infallible;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+/// The type of a file in the `FILE` command
+///
+/// Besides the types defined by CDRWIN, common nonstandard ones written by rippers are recognized,
+/// and the rest are kept in `Other`
+///
+/// ```rust
+/// use cuna::file_type::{ByteOrder, FileType};
+///
+/// assert_eq!("wave".parse::<FileType>().unwrap(), FileType::Wave);
+/// assert_eq!(FileType::from("FLAC"), FileType::Flac);
+/// assert_eq!(FileType::Motorola.byte_order(), Some(ByteOrder::BigEndian));
+/// assert!(FileType::Binary.is_raw());
+/// assert_eq!(FileType::from_extension("CDImage.ape"), Some(FileType::Ape));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub enum FileType {
+    /// Raw little-endian data, e.g. a bin image
+    Binary,
+    /// Raw big-endian data
+    Motorola,
+    Aiff,
+    #[default]
+    Wave,
+    Mp3,
+    Flac,
+    Ape,
+    /// WavPack, written as `WV`
+    WavPack,
+    Other(String),
+}
+/// The byte order of samples in a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ByteOrder {
+    LittleEndian,
+    BigEndian,
+}
+
+impl FileType {
+    /// All the types with a name, i.e. except `Other`
+    pub const KNOWN: [Self; 8] = [
+        Self::Binary,
+        Self::Motorola,
+        Self::Aiff,
+        Self::Wave,
+        Self::Mp3,
+        Self::Flac,
+        Self::Ape,
+        Self::WavPack,
+    ];
+
+    /// Returns the type as written in cue sheets
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Binary => "BINARY",
+            Self::Motorola => "MOTOROLA",
+            Self::Aiff => "AIFF",
+            Self::Wave => "WAVE",
+            Self::Mp3 => "MP3",
+            Self::Flac => "FLAC",
+            Self::Ape => "APE",
+            Self::WavPack => "WV",
+            Self::Other(s) => s,
+        }
+    }
+    /// Returns `true` if the type is defined by CDRWIN
+    pub const fn is_standard(&self) -> bool {
+        matches!(
+            self,
+            Self::Binary | Self::Motorola | Self::Aiff | Self::Wave | Self::Mp3
+        )
+    }
+    /// Returns `true` for raw CD images, i.e. `BINARY` and `MOTOROLA`
+    pub const fn is_raw(&self) -> bool {
+        matches!(self, Self::Binary | Self::Motorola)
+    }
+    /// Returns the byte order of uncompressed samples, or `None` for compressed or unknown types
+    pub const fn byte_order(&self) -> Option<ByteOrder> {
+        match self {
+            Self::Binary | Self::Wave => Some(ByteOrder::LittleEndian),
+            Self::Motorola | Self::Aiff => Some(ByteOrder::BigEndian),
+            _ => None,
+        }
+    }
+    /// Infers the type from the extension of `path`, ignoring ASCII case
+    pub fn from_extension<P: AsRef<Path>>(path: P) -> Option<Self> {
+        let ext = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "bin" | "img" | "raw" => Some(Self::Binary),
+            "aif" | "aiff" | "aifc" => Some(Self::Aiff),
+            "wav" => Some(Self::Wave),
+            "mp3" => Some(Self::Mp3),
+            "flac" => Some(Self::Flac),
+            "ape" => Some(Self::Ape),
+            "wv" => Some(Self::WavPack),
+            _ => None,
+        }
+    }
+    pub const fn is_other(&self) -> bool {
+        matches!(self, Self::Other(_))
+    }
+}
+impl FromStr for FileType {
+    type Err = Infallible;
+
+    /// Parses a type ignoring ASCII case, which never fails
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self::from(s))
+    }
+}
+impl From<&str> for FileType {
+    fn from(s: &str) -> Self {
+        Self::KNOWN
+            .into_iter()
+            .find(|tp| tp.as_str().eq_ignore_ascii_case(s))
+            .unwrap_or_else(|| Self::Other(s.to_owned()))
+    }
+}
+impl From<String> for FileType {
+    fn from(s: String) -> Self {
+        match Self::from(s.as_str()) {
+            Self::Other(_) => Self::Other(s),
+            tp => tp,
+        }
+    }
+}
+impl fmt::Display for FileType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
@@ -0,0 +1,176 @@
+use crate::error::InvalidArgument;
+use std::fmt;
+use std::iter::FromIterator;
+use std::ops::BitOr;
+use std::str::FromStr;
+
+/// A sub-code flag of a track in the `FLAGS` command
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Flag {
+    /// `DCP`, digital copy permitted
+    Dcp,
+    /// `4CH`, four channel audio
+    FourCh,
+    /// `PRE`, pre-emphasis enabled
+    Pre,
+    /// `SCMS`, serial copy management system
+    Scms,
+    /// `DATA`, set for all non-audio tracks
+    Data,
+}
+/// The set of flags of a track
+///
+/// Known flags are stored as bits, while unknown ones,
+/// which are only accepted by [`Flags::parse_lossy()`](Flags::parse_lossy),
+/// are kept as they are written
+///
+/// ```rust
+/// use cuna::flag::{Flag, Flags};
+///
+/// let flags: Flags = "DCP  PRE".parse().unwrap();
+/// assert!(flags.contains(Flag::Pre));
+/// assert!(!flags.contains(Flag::FourCh));
+/// assert_eq!(flags, Flag::Dcp | Flag::Pre);
+/// assert_eq!(flags.to_string(), "DCP PRE");
+/// assert!("PRE4CH".parse::<Flags>().is_err());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Flags {
+    bits: u8,
+    unknown: Vec<String>,
+}
+
+impl Flag {
+    pub const ALL: [Self; 5] = [Self::Dcp, Self::FourCh, Self::Pre, Self::Scms, Self::Data];
+
+    /// Returns the flag as written in cue sheets
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Dcp => "DCP",
+            Self::FourCh => "4CH",
+            Self::Pre => "PRE",
+            Self::Scms => "SCMS",
+            Self::Data => "DATA",
+        }
+    }
+    const fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+impl FromStr for Flag {
+    type Err = InvalidArgument;
+
+    /// Parses a flag, ignoring ASCII case
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|flag| flag.as_str().eq_ignore_ascii_case(s))
+            .ok_or(InvalidArgument::InvalidFlag)
+    }
+}
+impl fmt::Display for Flag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+impl BitOr for Flag {
+    type Output = Flags;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Flags::from_iter([self, rhs])
+    }
+}
+impl Flags {
+    pub const fn new() -> Self {
+        Self {
+            bits: 0,
+            unknown: Vec::new(),
+        }
+    }
+    /// Parses flags separated by whitespaces, keeping unknown flags instead of failing
+    ///
+    /// ```rust
+    /// use cuna::flag::{Flag, Flags};
+    ///
+    /// let flags = Flags::parse_lossy("DCP PRE4CH");
+    /// assert!(flags.contains(Flag::Dcp));
+    /// assert_eq!(flags.unknown(), &["PRE4CH".to_owned()]);
+    /// assert_eq!(flags.to_string(), "DCP PRE4CH");
+    /// ```
+    pub fn parse_lossy(s: &str) -> Self {
+        let mut flags = Self::new();
+        for token in s.split_whitespace() {
+            match token.parse() {
+                Ok(flag) => flags.insert(flag),
+                Err(_) => flags.unknown.push(token.to_owned()),
+            }
+        }
+        flags
+    }
+    pub const fn contains(&self, flag: Flag) -> bool {
+        self.bits & flag.bit() != 0
+    }
+    pub fn insert(&mut self, flag: Flag) {
+        self.bits |= flag.bit()
+    }
+    pub fn remove(&mut self, flag: Flag) {
+        self.bits &= !flag.bit()
+    }
+    /// Returns `true` if there are neither known nor unknown flags
+    pub fn is_empty(&self) -> bool {
+        self.bits == 0 && self.unknown.is_empty()
+    }
+    /// An iterator over the known flags in the order of [`Flag::ALL`]
+    pub fn iter(&self) -> impl Iterator<Item = Flag> + '_ {
+        Flag::ALL
+            .into_iter()
+            .filter(move |&flag| self.contains(flag))
+    }
+    /// Returns the flags which are not recognized
+    pub fn unknown(&self) -> &[String] {
+        &self.unknown
+    }
+    /// Returns `true` if copying is permitted, i.e. `DCP` is set
+    pub const fn copy_permitted(&self) -> bool {
+        self.contains(Flag::Dcp)
+    }
+    /// Returns `true` if pre-emphasis is enabled, i.e. `PRE` is set
+    pub const fn pre_emphasis(&self) -> bool {
+        self.contains(Flag::Pre)
+    }
+}
+impl FromStr for Flags {
+    type Err = InvalidArgument;
+
+    /// Parses flags separated by whitespaces, failing on unknown flags
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split_whitespace().map(Flag::from_str).collect()
+    }
+}
+impl fmt::Display for Flags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut flags: Vec<_> = self.iter().map(Flag::as_str).collect();
+        flags.extend(self.unknown.iter().map(String::as_str));
+        write!(f, "{}", flags.join(" "))
+    }
+}
+impl FromIterator<Flag> for Flags {
+    fn from_iter<T: IntoIterator<Item = Flag>>(iter: T) -> Self {
+        let mut flags = Self::new();
+        iter.into_iter().for_each(|flag| flags.insert(flag));
+        flags
+    }
+}
+impl Extend<Flag> for Flags {
+    fn extend<T: IntoIterator<Item = Flag>>(&mut self, iter: T) {
+        iter.into_iter().for_each(|flag| self.insert(flag))
+    }
+}
+impl BitOr<Flag> for Flags {
+    type Output = Self;
+
+    fn bitor(mut self, rhs: Flag) -> Self::Output {
+        self.insert(rhs);
+        self
+    }
+}
@@ -0,0 +1,534 @@
+use crate::address::Lba;
+use crate::audio::is_filled;
+use crate::audio::is_silent;
+use crate::cuna::Cuna;
+use crate::error::GapError;
+use crate::error::LayoutError;
+use crate::error::TimeOutOfRange;
+use crate::image::file_extents;
+use crate::layout::DiscLayout;
+use crate::length::FileLengthProvider;
+use crate::length::LocalFiles;
+use crate::length::RAW_SECTOR_SIZE;
+use crate::time::TimeOffset;
+use crate::time::TimeStamp;
+use crate::track::Disc;
+use crate::track::Index;
+use std::fs::File;
+use std::io;
+use std::io::BufReader;
+use std::ops::Range;
+
+/// Where a sheet with one `FILE` per track stores the gap before each track
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum GapStyle {
+    /// At the end of the previous file, with `INDEX 01` after the next `FILE` as EAC writes
+    ///
+    /// The gap of the first track stays at the start of its file
+    Appended,
+    /// At the start of the file of the track, between `INDEX 00` and `INDEX 01`
+    Prepended,
+    /// Not in any file, written as `PREGAP`
+    LeftOut,
+}
+
+/// Tells whether a part of a file holds only digital silence
+///
+/// `index` is the position of `file` in [`Cuna::files`]
+///
+/// Implemented for closures, [`AssumeSilent`] and [`LocalFiles`],
+/// which reads the samples of uncompressed WAVE and AIFF files with [`is_silent()`]
+/// and the sectors of `BINARY` and `MOTOROLA` files
+pub trait SilenceProbe {
+    fn is_silent(&self, index: usize, file: &Disc, range: Range<TimeStamp>) -> io::Result<bool>;
+}
+/// Trusts every gap to be silent without reading the files
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Default)]
+pub struct AssumeSilent;
+/// The gaps around a track, see [`Cuna::gaps()`]
+#[derive(Debug, PartialEq, Eq, Clone, Hash, Default)]
+pub struct TrackGaps {
+    pub number: u8,
+    /// The audio stored between `INDEX 00` and `INDEX 01`,
+    /// `None` if there is no `INDEX 00`, or if the gap crosses a `FILE` boundary
+    /// unless it is measured with [`Cuna::gaps_with_lengths()`]
+    pub stored: Option<TimeStamp>,
+    /// The silence generated by `PREGAP`
+    pub generated: Option<TimeStamp>,
+    pub postgap: Option<TimeStamp>,
+    /// Indexes after `INDEX 01`
+    pub sub_indexes: Vec<Index>,
+    /// Whether `INDEX 00` and `INDEX 01` are in different files
+    pub crosses_file: bool,
+}
+/// A change to a file needed to keep it matching a rewritten sheet
+///
+/// Positions are in the file after the previous edits are applied
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum GapEdit {
+    /// Inserts silence at `at`, or at the end of the file if `None`
+    Insert {
+        file: usize,
+        at: Option<TimeStamp>,
+        length: TimeStamp,
+    },
+    /// Removes the audio from `at`
+    Remove {
+        file: usize,
+        at: TimeStamp,
+        length: TimeStamp,
+    },
+}
+/// What a gap normalization did
+#[derive(Debug, PartialEq, Eq, Clone, Hash, Default)]
+pub struct GapReport {
+    /// Changes to the files, in the order they have to be applied
+    pub edits: Vec<GapEdit>,
+    /// Tracks whose gaps hold audio, which are left unchanged
+    pub not_silent: Vec<u8>,
+}
+
+impl TrackGaps {
+    /// Returns the whole gap before `INDEX 01`,
+    /// or `None` if it crosses a `FILE` boundary and is not measured
+    pub fn pregap(&self) -> Option<TimeStamp> {
+        if self.crosses_file && self.stored.is_none() {
+            return None;
+        }
+        Some(self.stored.unwrap_or_default() + self.generated.unwrap_or_default())
+    }
+}
+impl<F> SilenceProbe for F
+where
+    F: Fn(usize, &Disc, Range<TimeStamp>) -> io::Result<bool>,
+{
+    fn is_silent(&self, index: usize, file: &Disc, range: Range<TimeStamp>) -> io::Result<bool> {
+        self(index, file, range)
+    }
+}
+impl SilenceProbe for AssumeSilent {
+    fn is_silent(&self, _: usize, _: &Disc, _: Range<TimeStamp>) -> io::Result<bool> {
+        Ok(true)
+    }
+}
+impl SilenceProbe for LocalFiles {
+    fn is_silent(&self, _: usize, file: &Disc, range: Range<TimeStamp>) -> io::Result<bool> {
+        let mut f = BufReader::new(File::open(self.path(file))?);
+        if !file.format.is_raw() {
+            return is_silent(f, range);
+        }
+        let size = f.get_ref().metadata()?.len();
+        let extents = file_extents(0, file, size, false)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        // positions before the first track are in its mode
+        let offset = |pos: TimeStamp| {
+            let extent = extents.iter().rev().find(|e| e.start <= pos);
+            let extent = extent.or(extents.first())?;
+            let sectors = pos.diff(extent.start).total_frames();
+            let offset = extent.bytes.start as i64 + sectors * extent.sector_size as i64;
+            Some(offset.clamp(0, size as i64) as u64)
+        };
+        let bytes = match (offset(range.start), offset(range.end)) {
+            (Some(start), Some(end)) => start..end,
+            // a file without tracks holds audio sectors
+            _ => {
+                let offset = |pos: TimeStamp| (pos.total_frames_u64() * RAW_SECTOR_SIZE).min(size);
+                offset(range.start)..offset(range.end)
+            }
+        };
+        is_filled(&mut f, bytes, 0)
+    }
+}
+
+impl Cuna {
+    /// Rewrites a sheet with one `FILE` per track to store its gaps in `style`
+    ///
+    /// `lengths` are the lengths of the files in order.
+    /// Returns the new sheet and the lengths its files should have,
+    /// audio moved between files has to be split and joined accordingly,
+    /// and gaps which were not in any file have to be filled with silence
+    ///
+    /// Fails with [`LayoutError::TrackPerFile`] if any `FILE` holds more than one track,
+    /// so sheets mixing a single image with per-track files cannot be converted
+    ///
+    /// ```rust
+    /// use cuna::gap::GapStyle;
+    /// use cuna::time::TimeStamp;
+    /// use cuna::Cuna;
+    ///
+    /// let sheet = Cuna::new(r#"FILE "01.wav" WAVE
+    ///   TRACK 01 AUDIO
+    ///     INDEX 01 00:00:00
+    ///   TRACK 02 AUDIO
+    ///     INDEX 00 03:58:00
+    /// FILE "02.wav" WAVE
+    ///     INDEX 01 00:00:00"#).unwrap();
+    /// let lengths = [TimeStamp::new(4, 0, 0), TimeStamp::new(3, 0, 0)];
+    /// let (prepended, lengths) = sheet.convert_gaps(&lengths, GapStyle::Prepended).unwrap();
+    /// assert_eq!(lengths, vec![TimeStamp::new(3, 58, 0), TimeStamp::new(3, 2, 0)]);
+    /// assert_eq!(prepended[1][0].index()[1].begin_time, TimeStamp::new(0, 2, 0));
+    /// ```
+    pub fn convert_gaps(
+        &self,
+        lengths: &[TimeStamp],
+        style: GapStyle,
+    ) -> Result<(Cuna, Vec<TimeStamp>), LayoutError> {
+        let layout = DiscLayout::new(self, lengths)?;
+        for file in 0..self.files.len() {
+            let owners = layout.tracks.iter().filter(|t| t.file == file).count();
+            if owners != 1 {
+                return Err(LayoutError::TrackPerFile(file));
+            }
+        }
+        let since = |pos: Lba, base: Lba| (pos - base).to_timestamp().unwrap_or_default();
+        let mut sheet = Cuna {
+            header: self.header.clone(),
+            files: Vec::with_capacity(self.files.len()),
+            comments: self.comments.clone(),
+        };
+        let mut new_lengths = Vec::with_capacity(layout.tracks.len());
+        for (i, (source, track)) in self.tracks().zip(&layout.tracks).enumerate() {
+            let postgap = TimeOffset::try_from(source.postgap().copied().unwrap_or_default())?;
+            // the gap between sessions is in no file
+            let next = match layout.tracks.get(i + 1) {
+                Some(next) if next.session == track.session => next.start(),
+                _ => layout
+                    .session_lead_out(track.session)
+                    .unwrap_or(layout.lead_out),
+            };
+            let end = next.checked_add(-postgap).ok_or(TimeOutOfRange)?;
+            let gap = track.pregap();
+            // the first track of the disc or of a session has no previous file to take its gap
+            let first = i == 0 || layout.tracks[i - 1].session != track.session;
+            let appended = style == GapStyle::Appended && !first && !gap.is_zero();
+            let base = match style {
+                GapStyle::LeftOut => track.index01,
+                _ if appended => track.index01,
+                _ => track.start(),
+            };
+            let mut rewritten = source.clone();
+            rewritten.index.clear();
+            rewritten.pregap = None;
+            if !gap.is_zero() {
+                match style {
+                    GapStyle::LeftOut => rewritten.pregap = Some(gap),
+                    _ if appended => {
+                        let prev = new_lengths.last_mut().unwrap();
+                        rewritten.push_index(Index::new(0, *prev));
+                        *prev += gap;
+                    }
+                    _ => rewritten.push_index(Index::new(0, TimeStamp::ZERO)),
+                }
+            }
+            let indexes = track
+                .indexes
+                .iter()
+                .filter(|&&(id, pos)| id > 0 && pos >= track.index01)
+                .map(|&(id, pos)| Index::new(id, since(pos, base)));
+            let source_file = &self.files[track.file];
+            let mut file = Disc::new(source_file.name.clone(), source_file.format.clone());
+            file.comments = source_file.comments.clone();
+            if appended {
+                file.continued.extend(indexes);
+                sheet.files.last_mut().unwrap().push_track(rewritten);
+            } else {
+                rewritten.index.extend(indexes);
+                file.push_track(rewritten);
+            }
+            sheet.files.push(file);
+            new_lengths.push(since(end, base));
+        }
+        Ok((sheet, new_lengths))
+    }
+}
+impl Cuna {
+    /// Lists the gaps of every track in order
+    ///
+    /// ```rust
+    /// use cuna::time::TimeStamp;
+    /// use cuna::Cuna;
+    ///
+    /// let sheet = Cuna::new(r#"FILE "01.wav" WAVE
+    ///   TRACK 01 AUDIO
+    ///     PREGAP 00:02:00
+    ///     INDEX 01 00:00:00
+    ///     INDEX 02 01:00:00
+    ///   TRACK 02 AUDIO
+    ///     INDEX 00 03:58:00
+    /// FILE "02.wav" WAVE
+    ///     INDEX 01 00:00:00"#).unwrap();
+    /// let gaps = sheet.gaps();
+    /// assert_eq!(gaps[0].pregap(), Some(TimeStamp::new(0, 2, 0)));
+    /// assert_eq!(gaps[0].sub_indexes[0].id(), 2);
+    /// assert!(gaps[1].crosses_file);
+    /// assert_eq!(gaps[1].pregap(), None);
+    /// ```
+    pub fn gaps(&self) -> Vec<TrackGaps> {
+        // no file is measured, so nothing can fail
+        self.measure_gaps(|_, _| Ok(None)).unwrap_or_default()
+    }
+    /// Like [`gaps()`](Cuna::gaps), but also measures the gaps which cross a `FILE` boundary
+    ///
+    /// `lengths` is asked for the length of the file holding `INDEX 00` of those tracks,
+    /// as in the layout EAC writes with gaps appended to the previous file
+    ///
+    /// ```rust
+    /// use cuna::time::TimeStamp;
+    /// use cuna::Cuna;
+    ///
+    /// let sheet = Cuna::new(r#"FILE "01.wav" WAVE
+    ///   TRACK 01 AUDIO
+    ///     INDEX 01 00:00:00
+    ///   TRACK 02 AUDIO
+    ///     INDEX 00 03:58:00
+    /// FILE "02.wav" WAVE
+    ///     INDEX 01 00:00:00"#).unwrap();
+    /// let gaps = sheet.gaps_with_lengths(&[TimeStamp::new(4, 0, 0)][..]).unwrap();
+    /// assert!(gaps[1].crosses_file);
+    /// assert_eq!(gaps[1].pregap(), Some(TimeStamp::new(0, 2, 0)));
+    /// ```
+    pub fn gaps_with_lengths<P>(&self, lengths: &P) -> io::Result<Vec<TrackGaps>>
+    where
+        P: FileLengthProvider + ?Sized,
+    {
+        self.measure_gaps(|fi, file| lengths.file_length(fi, file).map(Some))
+    }
+    fn measure_gaps<F>(&self, length: F) -> io::Result<Vec<TrackGaps>>
+    where
+        F: Fn(usize, &Disc) -> io::Result<Option<TimeStamp>>,
+    {
+        let mut gaps = Vec::new();
+        for (fi, file) in self.files.iter().enumerate() {
+            for (ti, track) in file.tracks().iter().enumerate() {
+                let mut indexes: Vec<_> = track.index().iter().map(|i| (*i, false)).collect();
+                // the rest of the last track may be after the next `FILE`
+                if ti + 1 == file.tracks().len() {
+                    if let Some(next) = self.files.get(fi + 1) {
+                        indexes.extend(next.continued().iter().map(|i| (*i, true)));
+                    }
+                }
+                let i00 = indexes.iter().find(|(i, _)| i.id() == 0);
+                let i01 = indexes.iter().find(|(i, _)| i.id() == 1);
+                let crosses_file = matches!((i00, i01), (Some((_, false)), Some((_, true))));
+                let stored = match (i00, i01) {
+                    (Some((i00, false)), Some((i01, false))) => {
+                        i01.begin_time.checked_sub(i00.begin_time)
+                    }
+                    // the end of the file of `INDEX 00` and the start of the next one
+                    (Some((i00, false)), Some((i01, true))) => length(fi, file)?
+                        .and_then(|length| length.checked_sub(i00.begin_time))
+                        .map(|gap| gap + i01.begin_time),
+                    _ => None,
+                };
+                gaps.push(TrackGaps {
+                    number: track.id(),
+                    stored,
+                    generated: track.pregap().copied(),
+                    postgap: track.postgap().copied(),
+                    sub_indexes: indexes
+                        .iter()
+                        .filter(|(i, _)| i.id() > 1)
+                        .map(|(i, _)| *i)
+                        .collect(),
+                    crosses_file,
+                });
+            }
+        }
+        Ok(gaps)
+    }
+    /// Turns the audio between `INDEX 00` and `INDEX 01` into a `PREGAP` generated by the burner
+    ///
+    /// Gaps which `probe` finds not silent are kept and reported,
+    /// the others have to be cut from the files as listed in the report.
+    /// `probe` is given positions in the files before they are cut
+    ///
+    /// ```rust
+    /// use cuna::gap::{AssumeSilent, GapEdit};
+    /// use cuna::time::TimeStamp;
+    /// use cuna::Cuna;
+    ///
+    /// let mut sheet = Cuna::new(r#"FILE "a.wav" WAVE
+    ///   TRACK 01 AUDIO
+    ///     INDEX 01 00:00:00
+    ///   TRACK 02 AUDIO
+    ///     INDEX 00 03:00:00
+    ///     INDEX 01 03:02:00"#).unwrap();
+    /// let report = sheet.index00_to_pregap(&AssumeSilent).unwrap();
+    /// assert_eq!(sheet[0][1].pregap(), Some(&TimeStamp::new(0, 2, 0)));
+    /// assert_eq!(sheet[0][1].index()[0].begin_time, TimeStamp::new(3, 0, 0));
+    /// assert_eq!(report.edits, vec![GapEdit::Remove {
+    ///     file: 0,
+    ///     at: TimeStamp::new(3, 0, 0),
+    ///     length: TimeStamp::new(0, 2, 0),
+    /// }]);
+    /// ```
+    pub fn index00_to_pregap<S>(&mut self, probe: &S) -> Result<GapReport, GapError>
+    where
+        S: SilenceProbe + ?Sized,
+    {
+        let mut report = GapReport::default();
+        for (fi, file) in self.files.iter_mut().enumerate() {
+            // `probe` reads the file before any audio is removed
+            let mut removed = TimeStamp::ZERO;
+            for ti in 0..file.tracks.len() {
+                let track = &file.tracks[ti];
+                let (start, end) = match (track.get_index(0), track.get_index(1)) {
+                    (Some(i00), Some(i01)) if i00.begin_time < i01.begin_time => {
+                        (i00.begin_time, i01.begin_time)
+                    }
+                    _ => continue,
+                };
+                if !probe.is_silent(fi, file, start + removed..end + removed)? {
+                    report.not_silent.push(track.id());
+                    continue;
+                }
+                let gap = end - start;
+                removed += gap;
+                let track = &mut file.tracks[ti];
+                track.index.retain(|index| index.id() != 0);
+                track.pregap = Some(track.pregap.unwrap_or_default() + gap);
+                shift(file, end, -TimeOffset::try_from(gap)?);
+                report.edits.push(GapEdit::Remove {
+                    file: fi,
+                    at: start,
+                    length: gap,
+                });
+            }
+        }
+        Ok(report)
+    }
+    /// Turns every `PREGAP` into silence stored in the file before `INDEX 01`, marked by `INDEX 00`
+    pub fn pregap_to_index00(&mut self) -> Result<GapReport, GapError> {
+        let mut report = GapReport::default();
+        for (fi, file) in self.files.iter_mut().enumerate() {
+            for ti in 0..file.tracks.len() {
+                let track = &file.tracks[ti];
+                let (gap, at) = match (track.pregap, track.index.first()) {
+                    (Some(gap), Some(first)) => (gap, first.begin_time),
+                    _ => continue,
+                };
+                shift(file, at, TimeOffset::try_from(gap)?);
+                let track = &mut file.tracks[ti];
+                track.pregap = None;
+                match track.index.first_mut() {
+                    Some(first) if first.id() == 0 => first.begin_time = at,
+                    _ => track.index.insert(0, Index::new_unchecked(0, at)),
+                }
+                report.edits.push(GapEdit::Insert {
+                    file: fi,
+                    at: Some(at),
+                    length: gap,
+                });
+            }
+        }
+        Ok(report)
+    }
+    /// Turns every `POSTGAP` into silence stored in the file at the end of the track
+    ///
+    /// The silence goes to the next file if the track continues there
+    pub fn postgap_to_audio(&mut self) -> Result<GapReport, GapError> {
+        let mut report = GapReport::default();
+        for fi in 0..self.files.len() {
+            for ti in 0..self.files[fi].tracks.len() {
+                let gap = match self.files[fi].tracks[ti].postgap {
+                    Some(gap) => gap,
+                    None => continue,
+                };
+                let offset = TimeOffset::try_from(gap)?;
+                self.files[fi].tracks[ti].postgap = None;
+                let (fi, end) = track_end(&self.files, fi, ti);
+                if let Some(end) = end {
+                    shift(&mut self.files[fi], end, offset);
+                }
+                report.edits.push(GapEdit::Insert {
+                    file: fi,
+                    at: end,
+                    length: gap,
+                });
+            }
+        }
+        Ok(report)
+    }
+    /// Turns the last `length` of track `number` into a `POSTGAP`, if `probe` finds it silent
+    ///
+    /// `lengths` is asked for the length of the file if the track ends at the end of it,
+    /// which is the next file if the track continues there
+    pub fn audio_to_postgap<P, S>(
+        &mut self,
+        number: u8,
+        length: TimeStamp,
+        lengths: &P,
+        probe: &S,
+    ) -> Result<GapReport, GapError>
+    where
+        P: FileLengthProvider + ?Sized,
+        S: SilenceProbe + ?Sized,
+    {
+        let mut report = GapReport::default();
+        let (fi, ti) = self
+            .files
+            .iter()
+            .enumerate()
+            .find_map(|(fi, file)| {
+                let ti = file.tracks.iter().position(|t| t.id() == number)?;
+                Some((fi, ti))
+            })
+            .ok_or(GapError::NoSuchTrack(number))?;
+        let (end_fi, end) = track_end(&self.files, fi, ti);
+        let last = if end_fi == fi {
+            self.files[fi].tracks[ti].index.last()
+        } else {
+            self.files[end_fi].continued.last()
+        };
+        let last = last.map(|index| index.begin_time);
+        let file = &mut self.files[end_fi];
+        let end = match end {
+            Some(end) => end,
+            None => lengths.file_length(end_fi, file)?,
+        };
+        let start = match end.checked_sub(length) {
+            Some(start) if last.map_or(true, |last| last <= start) => start,
+            _ => return Err(GapError::TooLong(number)),
+        };
+        if !probe.is_silent(end_fi, file, start..end)? {
+            report.not_silent.push(number);
+            return Ok(report);
+        }
+        shift(file, end, -TimeOffset::try_from(length)?);
+        let track = &mut self.files[fi].tracks[ti];
+        track.postgap = Some(track.postgap.unwrap_or_default() + length);
+        report.edits.push(GapEdit::Remove {
+            file: end_fi,
+            at: start,
+            length,
+        });
+        Ok(report)
+    }
+}
+
+/// Moves all the indexes in `file` from `from` by `by`
+fn shift(file: &mut Disc, from: TimeStamp, by: TimeOffset) {
+    let tracks = file.tracks.iter_mut().flat_map(|t| t.index.iter_mut());
+    for index in file.continued.iter_mut().chain(tracks) {
+        if index.begin_time >= from {
+            index.begin_time = index.begin_time + by;
+        }
+    }
+}
+/// Returns the file where `track` in `file` ends and where the next track starts in it
+///
+/// The last track of a file continues into the next file if that starts with its indexes
+fn track_end(files: &[Disc], file: usize, track: usize) -> (usize, Option<TimeStamp>) {
+    match files.get(file + 1) {
+        Some(next) if track + 1 == files[file].tracks.len() && !next.continued.is_empty() => {
+            let first = next.tracks.first().and_then(|t| t.index.first());
+            (file + 1, first.map(|index| index.begin_time))
+        }
+        _ => (file, next_start(&files[file], track)),
+    }
+}
+/// Returns where the track after `track` starts in `file`
+fn next_start(file: &Disc, track: usize) -> Option<TimeStamp> {
+    let next = file.tracks.get(track + 1)?;
+    next.index.first().map(|index| index.begin_time)
+}
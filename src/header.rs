@@ -1,11 +1,13 @@
-use crate::error::ParseError;
+use crate::catalog::Catalog;
+use crate::parser::Command;
+use std::fmt;
 
 #[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
 pub struct Header {
     pub title: Vec<String>,
     pub performer: Vec<String>,
     pub songwriter: Vec<String>,
-    pub(crate) catalog: Option<u64>,
+    pub(crate) catalog: Option<Catalog>,
     pub cdtextfile: Option<String>,
 }
 
@@ -37,15 +39,11 @@ impl Header {
     pub fn push_songwriter(&mut self, songwriter: String) {
         self.songwriter.push(songwriter)
     }
-    pub fn catalog(&self) -> Option<u64> {
+    pub fn catalog(&self) -> Option<Catalog> {
         self.catalog
     }
-    pub fn set_catalog(&mut self, catalog: u64) -> Result<Option<u64>, ParseError> {
-        if len(catalog) == 13 {
-            Ok(self.catalog.replace(catalog))
-        } else {
-            Err(ParseError::syntax_error(catalog, "invalid catalog"))
-        }
+    pub fn set_catalog(&mut self, catalog: Catalog) -> Option<Catalog> {
+        self.catalog.replace(catalog)
     }
     pub fn cdtextfile(&self) -> Option<&str> {
         self.cdtextfile.as_deref()
@@ -54,8 +52,24 @@ impl Header {
         self.cdtextfile.replace(cdtextfile)
     }
 }
-
-#[inline]
-fn len(d: u64) -> usize {
-    (d as f32).log10() as usize + 1
+impl fmt::Display for Header {
+    /// Writes the disc-level commands, one per line
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(catalog) = self.catalog {
+            writeln!(f, "{}", Command::Catalog(catalog))?;
+        }
+        if let Some(cdtextfile) = self.cdtextfile() {
+            writeln!(f, "{}", Command::Cdtextfile(cdtextfile))?;
+        }
+        for performer in self.performer() {
+            writeln!(f, "{}", Command::Performer(performer))?;
+        }
+        for title in self.title() {
+            writeln!(f, "{}", Command::Title(title))?;
+        }
+        for songwriter in self.songwriter() {
+            writeln!(f, "{}", Command::Songwriter(songwriter))?;
+        }
+        Ok(())
+    }
 }
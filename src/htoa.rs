@@ -0,0 +1,98 @@
+use crate::cuna::Cuna;
+use crate::gap::SilenceProbe;
+use crate::mode::TrackMode;
+use crate::time::TimeStamp;
+use crate::track::Index;
+use crate::track::Track;
+use std::io;
+
+/// Hidden Track One Audio, the audio before `INDEX 01` of track 1
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Default)]
+pub struct Htoa {
+    /// Which `FILE` stores the hidden audio
+    pub file: usize,
+    /// Where the hidden audio starts in the file, at `INDEX 00` of the first track
+    pub start: TimeStamp,
+    /// Where the hidden audio ends in the file, at `INDEX 01` of the first track
+    pub end: TimeStamp,
+}
+
+impl Htoa {
+    pub fn length(&self) -> TimeStamp {
+        self.end - self.start
+    }
+}
+impl Cuna {
+    /// Returns the hidden audio before `TRACK 01`, if there is any
+    ///
+    /// Audio between `INDEX 00` and `INDEX 01` of track 1 is only hidden if `probe` finds it not silent,
+    /// otherwise it is an ordinary pregap
+    ///
+    /// ```rust
+    /// use cuna::gap::AssumeSilent;
+    /// use cuna::time::TimeStamp;
+    /// use cuna::track::Disc;
+    /// use cuna::Cuna;
+    /// use std::ops::Range;
+    ///
+    /// let mut sheet = Cuna::new(r#"FILE "a.wav" WAVE
+    ///   TRACK 01 AUDIO
+    ///     INDEX 00 00:00:00
+    ///     INDEX 01 00:32:12
+    ///   TRACK 02 AUDIO
+    ///     INDEX 01 04:00:00"#).unwrap();
+    /// let noisy = |_: usize, _: &Disc, _: Range<TimeStamp>| Ok(false);
+    /// let htoa = sheet.htoa(&noisy).unwrap().unwrap();
+    /// assert_eq!(htoa.length(), TimeStamp::new(0, 32, 12));
+    /// assert_eq!(sheet.htoa(&AssumeSilent).unwrap(), None);
+    ///
+    /// sheet.split_htoa(&noisy).unwrap();
+    /// assert_eq!(sheet[0][0].id(), 0);
+    /// assert_eq!(sheet[0][0].index()[0].begin_time, TimeStamp::new(0, 0, 0));
+    /// assert_eq!(sheet[0][1].index().len(), 1);
+    /// assert_eq!(sheet.htoa(&noisy).unwrap(), None);
+    /// ```
+    pub fn htoa<S>(&self, probe: &S) -> io::Result<Option<Htoa>>
+    where
+        S: SilenceProbe + ?Sized,
+    {
+        let found = self
+            .files
+            .iter()
+            .enumerate()
+            .find(|(_, disc)| !disc.tracks().is_empty());
+        let (file, disc) = match found {
+            Some((file, disc)) if disc.tracks()[0].id() == 1 => (file, disc),
+            _ => return Ok(None),
+        };
+        let first = &disc.tracks()[0];
+        let (start, end) = match (first.get_index(0), first.get_index(1)) {
+            (Some(i00), Some(i01)) if i00.begin_time < i01.begin_time => {
+                (i00.begin_time, i01.begin_time)
+            }
+            _ => return Ok(None),
+        };
+        if probe.is_silent(file, disc, start..end)? {
+            return Ok(None);
+        }
+        Ok(Some(Htoa { file, start, end }))
+    }
+    /// Moves the hidden audio before the first track to a virtual `TRACK 00`, so it can be split like the others
+    ///
+    /// Returns the hidden audio which was moved, see [`htoa()`](Cuna::htoa)
+    pub fn split_htoa<S>(&mut self, probe: &S) -> io::Result<Option<Htoa>>
+    where
+        S: SilenceProbe + ?Sized,
+    {
+        let htoa = match self.htoa(probe)? {
+            Some(htoa) => htoa,
+            None => return Ok(None),
+        };
+        let disc = &mut self.files[htoa.file];
+        disc.tracks[0].index.retain(|index| index.id() != 0);
+        let mut hidden = Track::new_unchecked(0, TrackMode::Audio);
+        hidden.push_index(Index::new_unchecked(1, htoa.start));
+        disc.tracks.insert(0, hidden);
+        Ok(Some(htoa))
+    }
+}
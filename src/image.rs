@@ -0,0 +1,147 @@
+use crate::audio::join_name;
+use crate::cuna::Cuna;
+use crate::error::ImageError;
+use crate::error::LayoutError;
+use crate::mode::TrackMode;
+use crate::time::TimeStamp;
+use crate::track::Disc;
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+
+/// Where the tracks are stored in the raw image files of a cue sheet
+///
+/// A track takes the sectors from its first index to the first index of the next track in the same file,
+/// or to the end of the file, so its pregap is stored in its own mode.
+/// The sector size follows [`TrackMode::sector_size()`], and may change between tracks in one file
+///
+/// ```rust
+/// use cuna::image::ImageLayout;
+/// use cuna::time::TimeStamp;
+/// use cuna::Cuna;
+///
+/// let sheet = Cuna::new(r#"FILE "game.bin" BINARY
+///   TRACK 01 MODE1/2048
+///     INDEX 01 00:00:00
+///   TRACK 02 AUDIO
+///     INDEX 00 00:10:00
+///     INDEX 01 00:12:00"#).unwrap();
+/// let image = ImageLayout::new(&sheet, &[750 * 2048 + 300 * 2352]).unwrap();
+/// assert_eq!(image.tracks[0].bytes, 0..750 * 2048);
+/// assert_eq!(image.tracks[1].sectors, 300);
+/// assert_eq!(image.tracks[1].length(), TimeStamp::new(0, 4, 0));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct ImageLayout {
+    pub tracks: Vec<TrackExtent>,
+}
+/// The sectors of a track in a raw image file
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct TrackExtent {
+    pub number: u8,
+    /// Which `FILE` stores the track
+    pub file: usize,
+    pub mode: TrackMode,
+    pub sector_size: u32,
+    /// The first sector of the track in the file
+    pub start: TimeStamp,
+    pub sectors: u64,
+    /// The bytes of the track in the file
+    pub bytes: Range<u64>,
+}
+
+impl ImageLayout {
+    /// Computes the extents of all the tracks with the size of each `FILE` in bytes
+    pub fn new(sheet: &Cuna, sizes: &[u64]) -> Result<Self, LayoutError> {
+        if sheet.files.len() != sizes.len() {
+            return Err(LayoutError::FileCount {
+                expected: sheet.files.len(),
+                found: sizes.len(),
+            });
+        }
+        let mut tracks = Vec::new();
+        for (file, (disc, &size)) in sheet.files.iter().zip(sizes).enumerate() {
+            tracks.extend(file_extents(file, disc, size, true)?);
+        }
+        Ok(Self { tracks })
+    }
+    /// Reads the sizes of the files in `dir`, see [`ImageLayout::new()`]
+    pub fn open<P: AsRef<Path>>(sheet: &Cuna, dir: P) -> Result<Self, ImageError> {
+        let sizes = sheet
+            .files
+            .iter()
+            .map(|file| Ok(fs::metadata(join_name(dir.as_ref(), &file.name))?.len()))
+            .collect::<Result<Vec<_>, ImageError>>()?;
+        Ok(Self::new(sheet, &sizes)?)
+    }
+    /// Searches for a track by its number
+    pub fn track(&self, number: u8) -> Option<&TrackExtent> {
+        self.tracks.iter().find(|track| track.number == number)
+    }
+}
+impl TrackExtent {
+    /// Returns how long the track is, including its pregap stored in the file
+    pub fn length(&self) -> TimeStamp {
+        TimeStamp::from_frames_u64(self.sectors)
+    }
+}
+
+/// Splits a raw image file of `size` bytes into the tracks of `disc`
+///
+/// A partial sector at the end is an error if `strict`, or counted as a whole one
+pub(crate) fn file_extents(
+    file: usize,
+    disc: &Disc,
+    size: u64,
+    strict: bool,
+) -> Result<Vec<TrackExtent>, LayoutError> {
+    let mut extents: Vec<TrackExtent> = Vec::with_capacity(disc.tracks().len());
+    let mut offset = 0;
+    let mut sector = 0;
+    for track in disc.tracks() {
+        let number = track.id();
+        let start = track
+            .index()
+            .first()
+            .ok_or(LayoutError::MissingIndex(number))?
+            .begin_time;
+        let sector_size = track
+            .format()
+            .sector_size()
+            .ok_or(LayoutError::UnknownSectorSize(number))?;
+        let skipped = start
+            .total_frames_u64()
+            .checked_sub(sector)
+            .ok_or(LayoutError::Unordered(number))?;
+        // the sectors before the first track are counted in its mode
+        let skipped_size = extents.last().map_or(sector_size, |prev| prev.sector_size);
+        offset += skipped * skipped_size as u64;
+        if let Some(prev) = extents.last_mut() {
+            prev.sectors = skipped;
+            prev.bytes.end = offset;
+        }
+        if offset > size {
+            return Err(LayoutError::IndexOutOfFile(number));
+        }
+        sector = start.total_frames_u64();
+        extents.push(TrackExtent {
+            number,
+            file,
+            mode: track.format().clone(),
+            sector_size,
+            start,
+            sectors: 0,
+            bytes: offset..offset,
+        });
+    }
+    if let Some(last) = extents.last_mut() {
+        let rest = size - offset;
+        let sector_size = last.sector_size as u64;
+        if strict && rest % sector_size != 0 {
+            return Err(LayoutError::PartialSector(last.number));
+        }
+        last.sectors = rest.div_ceil(sector_size);
+        last.bytes.end = size;
+    }
+    Ok(extents)
+}
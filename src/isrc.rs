@@ -0,0 +1,95 @@
+use crate::error::InvalidArgument;
+use std::fmt;
+use std::str::FromStr;
+
+/// An International Standard Recording Code, made of
+/// a country code, a registrant code, a year and a designation code (`CC-XXX-YY-NNNNN`)
+///
+/// Parsing ignores ASCII case and accepts the hyphenated form.
+/// It is always stored and displayed in upper case without hyphens,
+/// while the alternate form `{:#}` inserts hyphens
+///
+/// ```rust
+/// use cuna::isrc::Isrc;
+///
+/// let isrc: Isrc = "jp-i10-12-00600".parse().unwrap();
+/// assert_eq!(isrc.country(), "JP");
+/// assert_eq!(isrc.registrant(), "I10");
+/// assert_eq!(isrc.year(), 12);
+/// assert_eq!(isrc.designation(), 600);
+/// assert_eq!(isrc.to_string(), "JPI101200600");
+/// assert_eq!(format!("{:#}", isrc), "JP-I10-12-00600");
+/// assert!("JPI1012006".parse::<Isrc>().is_err());
+/// assert!("J1I101200600".parse::<Isrc>().is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Isrc([u8; 12]);
+
+impl Isrc {
+    /// Returns the code in upper case without hyphens
+    pub fn as_str(&self) -> &str {
+        // Only ASCII alphanumerics are stored
+        std::str::from_utf8(&self.0).unwrap()
+    }
+    /// Returns the two-letter ISO 3166-1 country code
+    pub fn country(&self) -> &str {
+        &self.as_str()[..2]
+    }
+    /// Returns the three-character registrant code
+    pub fn registrant(&self) -> &str {
+        &self.as_str()[2..5]
+    }
+    /// Returns the last two digits of the year of reference
+    pub fn year(&self) -> u8 {
+        self.as_str()[5..7].parse().unwrap()
+    }
+    /// Returns the five-digit designation code
+    pub fn designation(&self) -> u32 {
+        self.as_str()[7..].parse().unwrap()
+    }
+    /// Returns the code in the `CC-XXX-YY-NNNNN` form
+    pub fn hyphenated(&self) -> String {
+        format!("{:#}", self)
+    }
+}
+impl FromStr for Isrc {
+    type Err = InvalidArgument;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let compact: Vec<u8> = match s.len() {
+            12 => s.bytes().collect(),
+            // Hyphens are only allowed between the parts
+            15 if [2, 6, 9].iter().all(|&at| s.as_bytes()[at] == b'-') => s
+                .bytes()
+                .enumerate()
+                .filter(|(i, _)| ![2, 6, 9].contains(i))
+                .map(|(_, b)| b)
+                .collect(),
+            _ => return Err(InvalidArgument::InvalidIsrc),
+        };
+        let mut code = [0; 12];
+        for (i, (to, b)) in code.iter_mut().zip(compact).enumerate() {
+            let valid = match i {
+                0..=1 => b.is_ascii_alphabetic(),
+                2..=4 => b.is_ascii_alphanumeric(),
+                _ => b.is_ascii_digit(),
+            };
+            if !valid {
+                return Err(InvalidArgument::InvalidIsrc);
+            }
+            *to = b.to_ascii_uppercase();
+        }
+        Ok(Self(code))
+    }
+}
+impl fmt::Display for Isrc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = self.as_str();
+        if f.alternate() {
+            write!(f, "{}-{}-{}-{}", &s[..2], &s[2..5], &s[5..7], &s[7..])
+        } else {
+            write!(f, "{}", s)
+        }
+    }
+}
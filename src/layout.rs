@@ -0,0 +1,301 @@
+use crate::address::Lba;
+use crate::address::LEAD_IN;
+use crate::cuna::Cuna;
+use crate::error::LayoutError;
+use crate::error::TimeOutOfRange;
+use crate::session::SESSION_GAP;
+use crate::time::TimeStamp;
+use std::iter;
+
+/// Positions of all the tracks on the disc, computed from a cue sheet and the lengths of its files
+///
+/// `PREGAP` and `POSTGAP` take up space on the disc but not in the files,
+/// and an index written after a `FILE` but before its first `TRACK` belongs to the previous track,
+/// like the pregaps in the non-compliant sheets written by EAC
+///
+/// Each new [`Session`](crate::session::Session) starts [`SESSION_GAP`] frames after the end of the previous one,
+/// which includes the pregap of its first track
+///
+/// ```rust
+/// use cuna::address::Lba;
+/// use cuna::layout::DiscLayout;
+/// use cuna::time::TimeStamp;
+/// use cuna::Cuna;
+///
+/// let sheet = Cuna::new(r#"FILE "01.wav" WAVE
+///   TRACK 01 AUDIO
+///     INDEX 01 00:00:00
+///   TRACK 02 AUDIO
+///     INDEX 00 03:58:40
+/// FILE "02.wav" WAVE
+///     INDEX 01 00:00:00"#).unwrap();
+/// let lengths = [TimeStamp::new(4, 0, 0), TimeStamp::new(3, 0, 0)];
+/// let layout = DiscLayout::new(&sheet, &lengths).unwrap();
+/// let track = layout.track(2).unwrap();
+/// assert_eq!(track.index00, Some(Lba(17890)));
+/// assert_eq!(track.index01, Lba(18000));
+/// assert_eq!(track.file, 1);
+/// assert_eq!(track.length, TimeStamp::new(3, 0, 0));
+/// assert_eq!(layout.lead_out, Lba(31500));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct DiscLayout {
+    pub tracks: Vec<TrackLayout>,
+    /// The start of each `FILE`
+    pub files: Vec<Lba>,
+    /// The position after the last track
+    pub lead_out: Lba,
+    pub sessions: Vec<SessionLayout>,
+}
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct TrackLayout {
+    pub number: u8,
+    pub session: u8,
+    /// Which `FILE` contains `INDEX 01`
+    pub file: usize,
+    /// The start of the pregap, from `PREGAP` or else `INDEX 00`
+    pub index00: Option<Lba>,
+    pub index01: Lba,
+    /// All the indexes in the order they appear
+    pub indexes: Vec<(u8, Lba)>,
+    /// From `INDEX 01` to `INDEX 01` of the next track or the lead-out of its session,
+    /// which is how long the track is in the table of contents
+    pub length: TimeStamp,
+}
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct SessionLayout {
+    pub number: u8,
+    /// The number of the first track in the session
+    pub first_track: u8,
+    /// The position after the last track of the session
+    pub lead_out: Lba,
+}
+
+/// A track whose length is unknown until the next one is found
+struct Pending {
+    number: u8,
+    session: u8,
+    generated: Option<TimeStamp>,
+    indexes: Vec<(u8, TimeStamp, usize)>,
+}
+
+impl DiscLayout {
+    /// Computes the layout of `sheet` with the length of each `FILE` in order
+    pub fn new(sheet: &Cuna, lengths: &[TimeStamp]) -> Result<Self, LayoutError> {
+        if sheet.files.len() != lengths.len() {
+            return Err(LayoutError::FileCount {
+                expected: sheet.files.len(),
+                found: lengths.len(),
+            });
+        }
+        let mut files = Vec::with_capacity(lengths.len());
+        let mut pending: Vec<Pending> = Vec::new();
+        let mut file_start = TimeStamp::ZERO;
+        // `PREGAP`, `POSTGAP` and gaps between sessions seen so far
+        let mut inserted = TimeStamp::ZERO;
+        let mut session_of = sheet.track_sessions().into_iter();
+        // the lead-out of a session is known once the next one starts
+        let mut sessions: Vec<(u8, u8, TimeStamp)> = Vec::new();
+        for (file, (disc, &length)) in sheet.files.iter().zip(lengths).enumerate() {
+            files.push(Lba::try_from(add(file_start, inserted)?)?);
+            let place = |number, begin: TimeStamp, inserted| {
+                if begin > length {
+                    Err(LayoutError::IndexOutOfFile(number))
+                } else {
+                    add(add(file_start, inserted)?, begin)
+                }
+            };
+            if let Some(track) = pending.last_mut() {
+                for index in disc.continued() {
+                    let pos = place(track.number, index.begin_time, inserted)?;
+                    track.indexes.push((index.id(), pos, file));
+                }
+            }
+            for (i, track) in disc.tracks().iter().enumerate() {
+                let first = i == 0 && disc.continued().is_empty();
+                let session = session_of.next().unwrap_or(1);
+                let begin = track.index().first().map_or(length, |i| i.begin_time);
+                match sessions.last_mut() {
+                    Some((number, ..)) if *number == session => {}
+                    Some(last) => {
+                        last.2 = place(track.id(), begin, inserted)?;
+                        sessions.push((session, track.id(), TimeStamp::ZERO));
+                        let stored = track
+                            .get_index(1)
+                            .map_or(TimeStamp::ZERO, |i| i.begin_time.saturating_sub(begin));
+                        let pregap = add(stored, track.pregap().copied().unwrap_or_default())?;
+                        let gap = TimeStamp::from_frames(SESSION_GAP);
+                        inserted = add(
+                            inserted,
+                            gap.saturating_sub(pregap.min(TimeStamp::from_frames(LEAD_IN))),
+                        )?;
+                        if first {
+                            // the file is on the disc after the gap
+                            files[file] = Lba::try_from(add(file_start, inserted)?)?;
+                        }
+                    }
+                    None => sessions.push((session, track.id(), TimeStamp::ZERO)),
+                }
+                let mut current = Pending {
+                    number: track.id(),
+                    session,
+                    generated: None,
+                    indexes: Vec::with_capacity(track.index().len()),
+                };
+                if let Some(&pregap) = track.pregap() {
+                    current.generated = Some(place(track.id(), begin, inserted)?);
+                    inserted = add(inserted, pregap)?;
+                }
+                for index in track.index() {
+                    let pos = place(track.id(), index.begin_time, inserted)?;
+                    current.indexes.push((index.id(), pos, file));
+                }
+                pending.push(current);
+                if let Some(&postgap) = track.postgap() {
+                    inserted = add(inserted, postgap)?;
+                }
+            }
+            file_start = add(file_start, length)?;
+        }
+        let lead_out = add(file_start, inserted)?;
+        if let Some(last) = sessions.last_mut() {
+            last.2 = lead_out;
+        }
+
+        let mut tracks: Vec<TrackLayout> = Vec::with_capacity(pending.len());
+        let mut last = TimeStamp::ZERO;
+        for track in pending {
+            let mut index00 = None;
+            let mut index01 = None;
+            let mut indexes = Vec::with_capacity(track.indexes.len());
+            if let Some(pos) = track.generated {
+                last = last.max(pos);
+                index00 = Some(Lba::try_from(pos)?);
+            }
+            for &(id, pos, file) in &track.indexes {
+                if pos < last {
+                    return Err(LayoutError::Unordered(track.number));
+                }
+                last = pos;
+                let lba = Lba::try_from(pos)?;
+                match id {
+                    // the silence of `PREGAP` comes before `INDEX 00`
+                    0 => index00 = index00.or(Some(lba)),
+                    1 if index01.is_none() => index01 = Some((lba, file)),
+                    _ => {}
+                }
+                indexes.push((id, lba));
+            }
+            let (index01, file) = index01.ok_or(LayoutError::MissingIndex(track.number))?;
+            tracks.push(TrackLayout {
+                number: track.number,
+                session: track.session,
+                file,
+                index00,
+                index01,
+                indexes,
+                length: TimeStamp::ZERO,
+            });
+        }
+        let lead_out = Lba::try_from(lead_out)?;
+        let sessions = sessions
+            .into_iter()
+            .map(|(number, first_track, end)| {
+                Ok(SessionLayout {
+                    number,
+                    first_track,
+                    lead_out: Lba::try_from(end)?,
+                })
+            })
+            .collect::<Result<Vec<_>, LayoutError>>()?;
+        let ends: Vec<_> = tracks
+            .iter()
+            .zip(tracks.iter().skip(1).map(Some).chain(iter::once(None)))
+            .map(|(track, next)| match next {
+                Some(next) if next.session == track.session => next.index01,
+                _ => sessions
+                    .iter()
+                    .find(|session| session.number == track.session)
+                    .map_or(lead_out, |session| session.lead_out),
+            })
+            .collect();
+        for (track, end) in tracks.iter_mut().zip(ends) {
+            track.length = (end - track.index01).to_timestamp().unwrap_or_default();
+        }
+        Ok(Self {
+            tracks,
+            files,
+            lead_out,
+            sessions,
+        })
+    }
+    /// Computes the freedb disc ID from the table of contents
+    ///
+    /// All the sessions are included, as they are read by a drive
+    ///
+    /// ```rust
+    /// use cuna::time::TimeStamp;
+    /// use cuna::Cuna;
+    ///
+    /// let sheet = Cuna::new(r#"FILE "a.wav" WAVE
+    ///   TRACK 01 AUDIO
+    ///     INDEX 01 00:00:00
+    ///   TRACK 02 AUDIO
+    ///     INDEX 01 03:00:00"#).unwrap();
+    /// let layout = sheet.layout(&[TimeStamp::new(5, 0, 0)]).unwrap();
+    /// assert_eq!(layout.cddb_id(), 0x0d01_2c02);
+    /// ```
+    pub fn cddb_id(&self) -> u32 {
+        let seconds = |lba: Lba| (lba.0 + LEAD_IN as i32).max(0) as u32 / 75;
+        let digits = |mut n: u32| {
+            let mut sum = 0;
+            while n > 0 {
+                sum += n % 10;
+                n /= 10;
+            }
+            sum
+        };
+        let sum: u32 = self
+            .tracks
+            .iter()
+            .map(|track| digits(seconds(track.index01)))
+            .sum();
+        let first = self.tracks.first().map_or(self.lead_out, |t| t.index01);
+        let total = seconds(self.lead_out) - seconds(first);
+        (sum % 255) << 24 | total << 8 | self.tracks.len() as u32
+    }
+    /// Returns the lead-out of a session
+    pub fn session_lead_out(&self, number: u8) -> Option<Lba> {
+        self.sessions
+            .iter()
+            .find(|session| session.number == number)
+            .map(|session| session.lead_out)
+    }
+    /// Searches for a track by its number
+    pub fn track(&self, number: u8) -> Option<&TrackLayout> {
+        self.tracks.iter().find(|track| track.number == number)
+    }
+}
+impl TrackLayout {
+    /// Returns where the track starts including its pregap
+    pub fn start(&self) -> Lba {
+        self.index00.unwrap_or(self.index01)
+    }
+    /// Returns the position of the index with `id`
+    pub fn index(&self, id: u8) -> Option<Lba> {
+        self.indexes
+            .iter()
+            .find(|(i, _)| *i == id)
+            .map(|&(_, pos)| pos)
+    }
+    /// Returns the length from [`start()`](TrackLayout::start) to `INDEX 01`
+    pub fn pregap(&self) -> TimeStamp {
+        (self.index01 - self.start())
+            .to_timestamp()
+            .unwrap_or_default()
+    }
+}
+
+fn add(a: TimeStamp, b: TimeStamp) -> Result<TimeStamp, LayoutError> {
+    Ok(a.checked_add(b).ok_or(TimeOutOfRange)?)
+}
@@ -0,0 +1,128 @@
+use crate::audio::join_name;
+use crate::audio::AudioInfo;
+use crate::audio::CddaMismatch;
+use crate::cuna::Cuna;
+use crate::image::file_extents;
+use crate::time::Rounding;
+use crate::time::TimeStamp;
+use crate::track::Disc;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// Bytes in a raw CD sector
+pub const RAW_SECTOR_SIZE: u64 = 2352;
+
+/// Tells how long the file of a `FILE` command is, which cue sheets do not store
+///
+/// `index` is the position of `file` in [`Cuna::files`](crate::Cuna::files)
+///
+/// Implemented for lists of lengths, closures and [`LocalFiles`]
+///
+/// ```rust
+/// use cuna::time::TimeStamp;
+/// use cuna::Cuna;
+///
+/// let sheet = Cuna::new(r#"FILE "a.wav" WAVE
+///   TRACK 01 AUDIO
+///     INDEX 01 00:00:00
+///   TRACK 02 AUDIO
+///     INDEX 01 03:00:00"#).unwrap();
+/// let durations = sheet.track_durations(&[TimeStamp::new(5, 0, 0)][..]).unwrap();
+/// assert_eq!(durations, vec![TimeStamp::new(3, 0, 0), TimeStamp::new(2, 0, 0)]);
+/// ```
+pub trait FileLengthProvider {
+    fn file_length(&self, index: usize, file: &Disc) -> io::Result<TimeStamp>;
+}
+/// Reads the lengths of files in a directory
+///
+/// WAVE, AIFF and FLAC files are found by their headers,
+/// `BINARY` and `MOTOROLA` files are sized in the sectors of their tracks,
+/// see [`ImageLayout`](crate::image::ImageLayout)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LocalFiles {
+    base: PathBuf,
+}
+
+impl FileLengthProvider for [TimeStamp] {
+    fn file_length(&self, index: usize, _: &Disc) -> io::Result<TimeStamp> {
+        self.get(index)
+            .copied()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no length for this file"))
+    }
+}
+impl FileLengthProvider for Vec<TimeStamp> {
+    fn file_length(&self, index: usize, file: &Disc) -> io::Result<TimeStamp> {
+        self.as_slice().file_length(index, file)
+    }
+}
+impl<F> FileLengthProvider for F
+where
+    F: Fn(usize, &Disc) -> io::Result<TimeStamp>,
+{
+    fn file_length(&self, index: usize, file: &Disc) -> io::Result<TimeStamp> {
+        self(index, file)
+    }
+}
+impl LocalFiles {
+    /// Looks for files in `base`
+    pub fn new<P: Into<PathBuf>>(base: P) -> Self {
+        Self { base: base.into() }
+    }
+    /// Looks for files in the directory of a cue sheet
+    pub fn beside<P: AsRef<Path>>(cue: P) -> Self {
+        let base = cue.as_ref().parent().unwrap_or_else(|| Path::new(""));
+        Self::new(base)
+    }
+    pub fn base(&self) -> &Path {
+        &self.base
+    }
+    /// Returns where the file of `file` should be, see [`resolve_path()`](crate::audio::resolve_path)
+    pub fn path(&self, file: &Disc) -> PathBuf {
+        join_name(&self.base, &file.name)
+    }
+    /// Reads the header of the audio file of `file`
+    pub fn audio_info(&self, file: &Disc) -> io::Result<AudioInfo> {
+        AudioInfo::open(self.path(file))
+    }
+    /// Lists how each audio `FILE` in `sheet` differs from CD-DA
+    ///
+    /// `BINARY` and `MOTOROLA` files are skipped
+    pub fn check(&self, sheet: &Cuna) -> Vec<(usize, io::Result<Vec<CddaMismatch>>)> {
+        sheet
+            .files
+            .iter()
+            .enumerate()
+            .filter(|(_, file)| !file.format.is_raw())
+            .map(|(index, file)| {
+                (
+                    index,
+                    self.audio_info(file).map(|info| info.cdda_mismatches()),
+                )
+            })
+            .collect()
+    }
+}
+impl FileLengthProvider for LocalFiles {
+    /// Rounds a partial frame or sector at the end of a file up
+    fn file_length(&self, _: usize, file: &Disc) -> io::Result<TimeStamp> {
+        let path = self.path(file);
+        if file.format.is_raw() {
+            let size = fs::metadata(&path)?.len();
+            // a file without tracks, or continued from the previous file
+            if file.tracks().is_empty() {
+                return Ok(TimeStamp::from_frames_u64(size.div_ceil(RAW_SECTOR_SIZE)));
+            }
+            return match file_extents(0, file, size, false) {
+                Ok(extents) => Ok(extents
+                    .last()
+                    .map_or(TimeStamp::ZERO, |last| last.start + last.length())),
+                Err(e) => Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+            };
+        }
+        AudioInfo::open(&path)?
+            .length(Rounding::Ceil)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
@@ -1,8 +1,23 @@
+pub mod address;
+pub mod audio;
+pub mod catalog;
 pub mod comment;
 pub mod cuna;
+pub mod encoding;
 pub mod error;
+pub mod file_type;
+pub mod flag;
+pub mod gap;
 pub mod header;
+pub mod htoa;
+pub mod image;
+pub mod isrc;
+pub mod layout;
+pub mod length;
+pub mod mode;
 pub mod parser;
+pub mod sample;
+pub mod session;
 pub mod time;
 pub mod track;
 pub mod utils;
@@ -0,0 +1,127 @@
+use std::convert::Infallible;
+use std::fmt;
+use std::str::FromStr;
+
+/// The data type of a track in the `TRACK` command, as defined by CDRWIN
+///
+/// Values outside the specification are kept in `Other`
+///
+/// ```rust
+/// use cuna::mode::TrackMode;
+///
+/// let mode: TrackMode = "mode1/2352".parse().unwrap();
+/// assert_eq!(mode, TrackMode::Mode1_2352);
+/// assert_eq!(mode.sector_size(), Some(2352));
+/// assert_eq!(mode.to_string(), "MODE1/2352");
+/// assert_eq!(TrackMode::from("MODE3/2352"), TrackMode::Other("MODE3/2352".to_owned()));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub enum TrackMode {
+    /// Audio, 2352 bytes per sector
+    #[default]
+    Audio,
+    /// Karaoke CD+G, 2448 bytes per sector
+    Cdg,
+    /// CD-ROM Mode 1 data, cooked
+    Mode1_2048,
+    /// CD-ROM Mode 1 data, raw
+    Mode1_2352,
+    /// CD-ROM XA Mode 2 form 1 data, cooked
+    Mode2_2048,
+    /// CD-ROM XA Mode 2 form 2 data
+    Mode2_2324,
+    /// CD-ROM Mode 2 data
+    Mode2_2336,
+    /// CD-ROM Mode 2 data, raw
+    Mode2_2352,
+    /// CD-I Mode 2 data
+    Cdi2336,
+    /// CD-I Mode 2 data, raw
+    Cdi2352,
+    Other(String),
+}
+
+impl TrackMode {
+    /// All the modes defined by CDRWIN
+    pub const STANDARD: [Self; 10] = [
+        Self::Audio,
+        Self::Cdg,
+        Self::Mode1_2048,
+        Self::Mode1_2352,
+        Self::Mode2_2048,
+        Self::Mode2_2324,
+        Self::Mode2_2336,
+        Self::Mode2_2352,
+        Self::Cdi2336,
+        Self::Cdi2352,
+    ];
+
+    /// Returns the mode as written in cue sheets
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Audio => "AUDIO",
+            Self::Cdg => "CDG",
+            Self::Mode1_2048 => "MODE1/2048",
+            Self::Mode1_2352 => "MODE1/2352",
+            Self::Mode2_2048 => "MODE2/2048",
+            Self::Mode2_2324 => "MODE2/2324",
+            Self::Mode2_2336 => "MODE2/2336",
+            Self::Mode2_2352 => "MODE2/2352",
+            Self::Cdi2336 => "CDI/2336",
+            Self::Cdi2352 => "CDI/2352",
+            Self::Other(s) => s,
+        }
+    }
+    /// Returns the number of bytes per sector of the track stored in a raw image,
+    /// or `None` for nonstandard modes
+    pub const fn sector_size(&self) -> Option<u32> {
+        match self {
+            Self::Cdg => Some(2448),
+            Self::Mode1_2048 | Self::Mode2_2048 => Some(2048),
+            Self::Mode2_2324 => Some(2324),
+            Self::Mode2_2336 | Self::Cdi2336 => Some(2336),
+            Self::Audio | Self::Mode1_2352 | Self::Mode2_2352 | Self::Cdi2352 => Some(2352),
+            Self::Other(_) => None,
+        }
+    }
+    /// Returns `true` for `AUDIO` and `CDG` tracks
+    pub const fn is_audio(&self) -> bool {
+        matches!(self, Self::Audio | Self::Cdg)
+    }
+    /// Returns `true` for standard data tracks
+    pub const fn is_data(&self) -> bool {
+        !self.is_audio() && !self.is_other()
+    }
+    pub const fn is_other(&self) -> bool {
+        matches!(self, Self::Other(_))
+    }
+}
+impl FromStr for TrackMode {
+    type Err = Infallible;
+
+    /// Parses a mode ignoring ASCII case, which never fails
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self::from(s))
+    }
+}
+impl From<&str> for TrackMode {
+    fn from(s: &str) -> Self {
+        Self::STANDARD
+            .into_iter()
+            .find(|mode| mode.as_str().eq_ignore_ascii_case(s))
+            .unwrap_or_else(|| Self::Other(s.to_owned()))
+    }
+}
+impl From<String> for TrackMode {
+    fn from(s: String) -> Self {
+        match Self::from(s.as_str()) {
+            Self::Other(_) => Self::Other(s),
+            mode => mode,
+        }
+    }
+}
+impl fmt::Display for TrackMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
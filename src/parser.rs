@@ -1,16 +1,25 @@
+use crate::catalog::Catalog;
+use crate::error::Diagnostic;
 use crate::error::Error;
 use crate::error::InvalidArgument;
 use crate::error::ParseError;
+use crate::error::Severity;
+use crate::file_type::FileType;
+use crate::flag::Flags;
+use crate::isrc::Isrc;
 use crate::time::TimeStamp;
 use crate::track::Disc;
 use crate::track::Index;
 use crate::track::Track;
 use crate::utils;
 use crate::Cuna;
+use std::borrow::Cow;
 use std::fmt;
 use std::iter::Enumerate;
 use std::str::Lines;
 
+pub mod cst;
+
 pub type Parser<'a> = Parna<Enumerate<Lines<'a>>>;
 
 macro_rules! fail {
@@ -28,15 +37,15 @@ pub enum Command<'a> {
     Title(&'a str),
     Performer(&'a str),
     Songwriter(&'a str),
-    Catalog(u64),
+    Catalog(Catalog),
     Cdtextfile(&'a str),
-    File(&'a str, &'a str),
+    File(&'a str, FileType),
     Track(u8, &'a str),
     Index(u8, TimeStamp),
     Pregap(&'a str),
     Postgap(&'a str),
-    Isrc(&'a str),
-    Flags(&'a str),
+    Isrc(Cow<'a, str>),
+    Flags(Flags),
     Empty,
 }
 
@@ -48,10 +57,48 @@ pub enum Command<'a> {
 /// It only stores the original data
 /// and results will be written to `Cuna` which is passed to `Parna::parse*()`
 #[derive(Debug, Clone)]
-pub struct Parna<I>(I);
+pub struct Parna<I> {
+    lines: I,
+    options: ParseOptions,
+}
+/// Options to relax or tighten how commands are parsed
+///
+/// The default options follow the cue sheet specification
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct ParseOptions {
+    /// Keeps unknown flags in `FLAGS` commands instead of failing,
+    /// see [`Flags::parse_lossy()`](crate::flag::Flags::parse_lossy)
+    pub keep_unknown_flags: bool,
+    /// Rejects `ISRC` commands whose code is not a valid [`Isrc`](crate::isrc::Isrc),
+    /// and keeps valid codes in upper case without hyphens
+    ///
+    /// Otherwise the code is kept as written, and can be checked later with
+    /// [`Track::isrc_code()`](crate::track::Track::isrc_code)
+    pub strict_isrc: bool,
+    /// Rejects `CATALOG` commands whose check digit is wrong
+    ///
+    /// Otherwise the digits are kept as written, and can be checked later with
+    /// [`Catalog::is_valid()`]
+    pub strict_catalog: bool,
+}
 
 impl<'a> Command<'a> {
     pub fn new(s: &'a str) -> Result<Self, ParseError> {
+        Self::with_options(s, ParseOptions::default())
+    }
+    /// Like [`Command::new()`](Command::new), but parses arguments according to `options`
+    ///
+    /// ```rust
+    /// use cuna::parser::{Command, ParseOptions};
+    ///
+    /// let options = ParseOptions {
+    ///     keep_unknown_flags: true,
+    ///     ..ParseOptions::default()
+    /// };
+    /// assert!(Command::new("FLAGS DCP PRE4CH").is_err());
+    /// assert!(Command::with_options("FLAGS DCP PRE4CH", options).is_ok());
+    /// ```
+    pub fn with_options(s: &'a str, options: ParseOptions) -> Result<Self, ParseError> {
         let s = match s.trim() {
             "" => return Ok(Self::Empty),
             ts => ts,
@@ -65,15 +112,19 @@ impl<'a> Command<'a> {
             "title" => Ok(Self::Title(trimq(content))),
             "performer" => Ok(Self::Performer(trimq(content))),
             "songwriter" => Ok(Self::Songwriter(trimq(content))),
-            "catalog" => match utils::number(13)(content) {
-                Ok((_, catalog)) => Ok(Self::Catalog(catalog)),
-                Err(_) => fail!(syntax content, "invaild catalog"),
-            },
+            "catalog" if options.strict_catalog => Ok(Self::Catalog(content.trim().parse()?)),
+            "catalog" => Ok(Self::Catalog(Catalog::without_check_digit(content.trim())?)),
             "cdtextfile" => Ok(Self::Cdtextfile(trimq(content))),
-            "file" => match utils::quote_opt(content) {
-                Ok(("", _)) | Err(_) => Err(InvalidArgument::MissingArgument.into()),
-                Ok((format, path)) => Ok(Self::File(trimq(path), format.trim())),
-            },
+            "file" => {
+                let (path, format) = split_file(content);
+                match format
+                    .map(FileType::from)
+                    .or_else(|| FileType::from_extension(path))
+                {
+                    Some(format) => Ok(Self::File(path, format)),
+                    None => Err(InvalidArgument::MissingArgument.into()),
+                }
+            }
             "track" => match utils::token(content) {
                 Ok((format, id)) => Ok(Self::Track(parse_id(id)?, format)),
                 Err(_) => Err(InvalidArgument::MissingArgument.into()),
@@ -84,15 +135,25 @@ impl<'a> Command<'a> {
             },
             "pregap" => Ok(Self::Pregap(trimq(content))),
             "postgap" => Ok(Self::Postgap(trimq(content))),
-            "isrc" => Ok(Self::Isrc(trimq(content))),
-            "flags" => Ok(Self::Flags(trimq(content))),
+            "isrc" if options.strict_isrc => {
+                Ok(Self::Isrc(trimq(content).parse::<Isrc>()?.to_string().into()))
+            }
+            "isrc" => Ok(Self::Isrc(trimq(content).into())),
+            "flags" if options.keep_unknown_flags => Ok(Self::Flags(Flags::parse_lossy(content))),
+            "flags" => Ok(Self::Flags(content.parse()?)),
             _ => Err(ParseError::unexpected_token(command)),
         }
     }
     pub fn parse(&self, sheet: &mut Cuna) -> Result<(), ParseError> {
         match *self {
             Self::Empty => {}
-            Self::Rem(s) => sheet.comments.push(s.to_owned()),
+            Self::Rem(s) => match sheet.last_file_mut() {
+                Some(file) => match file.last_track_mut() {
+                    Some(tk) => tk.push_comment(s.to_owned()),
+                    None => file.push_comment(s.to_owned()),
+                },
+                None => sheet.comments.push(s.to_owned()),
+            },
             Self::Title(s) => match sheet.last_track_mut() {
                 Some(tk) => tk.push_title(s.to_owned()),
                 None => sheet.header.push_title(s.to_owned()),
@@ -112,20 +173,30 @@ impl<'a> Command<'a> {
             Self::Cdtextfile(s) => {
                 sheet.header.set_cdtextfile(s.to_owned());
             }
-            Self::File(name, format) => {
-                sheet.push_file(Disc::new(name.to_owned(), format.to_owned()));
+            Self::File(name, ref format) => {
+                sheet.push_file(Disc::new(name.to_owned(), format.clone()));
             }
             Self::Track(id, format) => match sheet.last_file_mut() {
-                Some(tk) => tk.push_track(Track::new_unchecked(id, format.to_owned())),
+                Some(tk) => tk.push_track(Track::new_unchecked(id, format.into())),
                 None => fail!(token "TRACK"),
             },
-            Self::Index(id, timestamp) => match sheet.last_track_mut() {
-                Some(tk) if tk.postgap.is_none() => {
-                    tk.push_index(Index::new_unchecked(id, timestamp))
+            Self::Index(id, timestamp) => {
+                let index = Index::new_unchecked(id, timestamp);
+                match sheet.last_file_mut() {
+                    // EAC puts the rest of a track which spans two files after the second `FILE`
+                    Some(file) if file.tracks.is_empty() => match sheet.tracks().next() {
+                        Some(_) => sheet.last_file_mut().unwrap().continued.push(index),
+                        None => fail!(token "INDEX"),
+                    },
+                    _ => match sheet.last_track_mut() {
+                        Some(tk) if tk.postgap.is_none() => tk.push_index(index),
+                        Some(_) => {
+                            fail!(syntax self, "Command `INDEX` should be before `POSTGAP`")
+                        }
+                        None => fail!(token "INDEX"),
+                    },
                 }
-                Some(_) => fail!(syntax self, "Command `INDEX` should be before `POSTGAP`"),
-                None => fail!(token "INDEX"),
-            },
+            }
             Self::Pregap(timestamp) => match sheet.last_track_mut() {
                 Some(tk) if tk.index.is_empty() && tk.pregap.is_none() => {
                     tk.set_pregep(timestamp.parse()?);
@@ -145,17 +216,19 @@ impl<'a> Command<'a> {
                 }
                 None => fail!(token "POSTGAP"),
             },
-            Self::Isrc(s) => match sheet.last_track_mut() {
+            Self::Isrc(ref s) => match sheet.last_track_mut() {
                 Some(tk) if tk.isrc.is_none() => {
-                    tk.set_isrc(s.to_owned());
+                    tk.set_isrc(s.to_string());
                 }
                 Some(_) => {
                     fail!(syntax self, "Multiple `ISRC` commands are not allowed in one `TRACK` scope")
                 }
                 None => fail!(token "ISRC"),
             },
-            Self::Flags(s) => match sheet.last_track_mut() {
-                Some(tk) if tk.flags.is_empty() => tk.push_flags(s.split(' ')),
+            Self::Flags(ref s) => match sheet.last_track_mut() {
+                Some(tk) if tk.flags.is_empty() => {
+                    tk.set_flags(s.clone());
+                }
                 Some(_) => {
                     fail!(syntax self, "Multiple `FLAGS` commands are not allowed in one `TRACK` scope")
                 }
@@ -174,13 +247,13 @@ impl fmt::Display for Command<'_> {
             Self::Songwriter(c) => write!(formatter, r#"SONGWRITER "{}""#, c),
             Self::Catalog(c) => write!(formatter, "CATALOG {}", c),
             Self::Cdtextfile(c) => write!(formatter, r#"CDTEXTFILE "{}""#, c),
-            Self::File(name, tp) => write!(formatter, r#"FILE "{}" {}"#, name, tp),
-            Self::Track(id, format) => write!(formatter, "TRACK {} {}", id, format),
-            Self::Index(id, timestamp) => write!(formatter, "INDEX {} {}", id, timestamp),
+            Self::File(name, ref tp) => write!(formatter, r#"FILE "{}" {}"#, name, tp),
+            Self::Track(id, format) => write!(formatter, "TRACK {:02} {}", id, format),
+            Self::Index(id, timestamp) => write!(formatter, "INDEX {:02} {}", id, timestamp),
             Self::Pregap(c) => write!(formatter, "PREGAP {}", c),
             Self::Postgap(c) => write!(formatter, "POSTGAP {}", c),
-            Self::Isrc(c) => write!(formatter, "ISRC {}", c),
-            Self::Flags(c) => write!(formatter, "FLAG {}", c),
+            Self::Isrc(ref c) => write!(formatter, "ISRC {}", c),
+            Self::Flags(ref c) => write!(formatter, "FLAGS {}", c),
             Self::Empty => Ok(()),
         }
     }
@@ -188,16 +261,16 @@ impl fmt::Display for Command<'_> {
 impl<'a> Parna<Enumerate<Lines<'a>>> {
     /// Returns a new Parser
     pub fn new(s: &'a str) -> Self {
-        Self(s.lines().enumerate())
+        Self::with_iter(s.lines().enumerate())
     }
 }
 impl<'a, I: Iterator<Item = &'a str>> Parna<Enumerate<I>> {
     pub fn from_lines(lines: I) -> Self {
-        Self(lines.enumerate())
+        Self::with_iter(lines.enumerate())
     }
     #[deprecated]
     pub fn set_lines(&mut self, lines: I) {
-        self.0 = lines.enumerate();
+        self.lines = lines.enumerate();
     }
 }
 impl<'a, I: Iterator<Item = (usize, &'a str)>> Parna<I> {
@@ -206,11 +279,22 @@ impl<'a, I: Iterator<Item = (usize, &'a str)>> Parna<I> {
     /// The `usize` represents which line is being parsed
     /// and the `&str` represents the actual data
     pub fn with_iter(it: I) -> Self {
-        Self(it)
+        Self {
+            lines: it,
+            options: ParseOptions::default(),
+        }
+    }
+    /// Sets the options used to parse each line
+    pub fn with_options(mut self, options: ParseOptions) -> Self {
+        self.options = options;
+        self
+    }
+    pub fn options(&self) -> ParseOptions {
+        self.options
     }
     /// Returns a mut reference to the internal iterator
     pub fn data(&mut self) -> &mut I {
-        self.0.by_ref()
+        self.lines.by_ref()
     }
     /// Parses one line and writes to `state`
     pub fn parse_next_line(&mut self, state: &mut Cuna) -> Result<(), Error> {
@@ -220,9 +304,9 @@ impl<'a, I: Iterator<Item = (usize, &'a str)>> Parna<I> {
     ///
     /// Each line will be parsed and written to `state` until an `Error` is returned
     pub fn parse_next_n_lines(&mut self, n: usize, state: &mut Cuna) -> Result<(), Error> {
-        for (at, line) in self.0.by_ref().take(n) {
+        for (at, line) in self.lines.by_ref().take(n) {
             let to_error = |e| Error::new(e, at + 1);
-            Command::new(line)
+            Command::with_options(line, self.options)
                 .map_err(to_error)?
                 .parse(state)
                 .map_err(to_error)?;
@@ -235,15 +319,39 @@ impl<'a, I: Iterator<Item = (usize, &'a str)>> Parna<I> {
     ///
     /// If all the lines are parsed successfully, an `Ok(())` will be returned
     pub fn parse(&mut self, state: &mut Cuna) -> Result<(), Error> {
-        for (at, line) in self.0.by_ref() {
+        for (at, line) in self.lines.by_ref() {
             let to_error = |e| Error::new(e, at + 1);
-            Command::new(line)
+            Command::with_options(line, self.options)
                 .map_err(to_error)?
                 .parse(state)
                 .map_err(to_error)?;
         }
         Ok(())
     }
+    /// Parses all the lines and writes to `state` without stopping at bad lines
+    ///
+    /// Bad lines are skipped and reported as [`Diagnostic`]s in the order they appear
+    ///
+    /// ```rust
+    /// use cuna::Cuna;
+    /// use cuna::error::Severity;
+    /// use cuna::parser::Parser;
+    ///
+    /// let mut sheet = Cuna::default();
+    /// let diagnostics = Parser::new("TITLE \"Hi\"\nTRACK 01 AUDIO\nUNKNOWN x").parse_with_diagnostics(&mut sheet);
+    /// assert_eq!(sheet.title(), &["Hi".to_owned()]);
+    /// assert_eq!(diagnostics[0].line(), 2);
+    /// assert_eq!(diagnostics[0].severity(), Severity::Error);
+    /// assert_eq!(diagnostics[1].line(), 3);
+    /// assert_eq!(diagnostics[1].severity(), Severity::Warning);
+    /// ```
+    pub fn parse_with_diagnostics(&mut self, state: &mut Cuna) -> Vec<Diagnostic> {
+        let options = self.options;
+        self.lines
+            .by_ref()
+            .filter_map(|(at, line)| parse_line(line, at + 1, options, state))
+            .collect()
+    }
 }
 impl<'a, I: Iterator<Item = (usize, &'a str)> + Clone> Parna<I> {
     /// Returns the current line to be parsed
@@ -258,17 +366,63 @@ impl<'a, I: Iterator<Item = (usize, &'a str)> + Clone> Parna<I> {
     }
     /// Like [`current_line()`](Parna::current_line), but returns line number at the same time
     pub fn current(&self) -> Option<(usize, &'a str)> {
-        self.0.clone().next()
+        self.lines.clone().next()
     }
 }
 
+/// Parses one line and writes to `state`, turning a failure into a [`Diagnostic`]
+pub(crate) fn parse_line(
+    line: &str,
+    at: usize,
+    options: ParseOptions,
+    state: &mut Cuna,
+) -> Option<Diagnostic> {
+    let command = match Command::with_options(line, options) {
+        Ok(command) => command,
+        // Unknown commands are ignored by most players, so they are not fatal
+        Err(e @ ParseError::UnexpectedToken(_)) => {
+            return Some(Diagnostic::new(Severity::Warning, e, at))
+        }
+        Err(e) => return Some(Diagnostic::new(Severity::Error, e, at)),
+    };
+    command
+        .parse(state)
+        .err()
+        .map(|e| Diagnostic::new(Severity::Error, e, at))
+}
+/// Splits the arguments of `FILE` into the unquoted path and the type if any
+///
+/// If the path is not quoted, the last word is taken as the type
+/// unless it is unknown and the whole arguments look like a path with a known extension
+pub(crate) fn split_file(content: &str) -> (&str, Option<&str>) {
+    let content = content.trim();
+    if let Ok((format, path)) = utils::quote(content) {
+        let format = format.trim();
+        return (path, Some(format).filter(|f| !f.is_empty()));
+    }
+    match content.rsplit_once(' ') {
+        Some((_, format))
+            if FileType::from(format).is_other() && FileType::from_extension(content).is_some() =>
+        {
+            (content, None)
+        }
+        Some((path, format)) => (path.trim_end(), Some(format)),
+        None => (content, None),
+    }
+}
 #[inline(always)]
 fn parse_id(s: &str) -> Result<u8, InvalidArgument> {
     Ok(utils::number(2)(s)
         .map_err(|_| InvalidArgument::InvalidId)?
         .1)
 }
+/// Removes the quotes around an argument, keeping those inside it
+///
+/// Cue sheets have no escapes, so `TITLE "Say "Hi""` is read up to the last quote
 #[inline(always)]
 fn trimq(s: &str) -> &str {
-    s.trim_matches('"')
+    match s.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+        Some(unquoted) => unquoted,
+        None => s.trim_matches('"'),
+    }
 }
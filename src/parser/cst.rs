@@ -0,0 +1,446 @@
+//! A lossless concrete syntax tree of a cue sheet
+//!
+//! Unlike [`Parna`](super::Parna), which only writes the meaning of each line into a [`Cuna`],
+//! [`SyntaxTree`] keeps every byte of the input: keyword case, indentation, spacing, quoting,
+//! blank lines, comments and line endings.
+//! Printing an unchanged tree gives back exactly the input,
+//! and edits only rewrite the lines they touch.
+//!
+//! ```rust
+//! use cuna::parser::cst::SyntaxTree;
+//!
+//! let src = "\u{feff}rem GENRE Pop\r\nTitle \"Departures\"\r\n\r\nFILE \"a.flac\" WAVE\r\n  TRACK 01 AUDIO\r\n    INDEX 01 00:00:00\r\n";
+//! let mut tree = SyntaxTree::parse(src);
+//! assert_eq!(tree.to_string(), src);
+//!
+//! tree.tracks_mut().next().unwrap().set("TITLE", "Departures");
+//! assert_eq!(
+//!     tree.to_string(),
+//!     src.replace("AUDIO\r\n", "AUDIO\r\n    TITLE \"Departures\"\r\n")
+//! );
+//! assert_eq!(tree.lower().unwrap()[0][0].title(), &["Departures".to_owned()]);
+//! ```
+use super::Command;
+use crate::error::Error;
+use crate::Cuna;
+use std::fmt;
+use std::slice;
+
+const BOM: char = '\u{feff}';
+/// Commands whose argument is quoted when written from scratch
+const QUOTED: [&str; 4] = ["TITLE", "PERFORMER", "SONGWRITER", "CDTEXTFILE"];
+
+/// One line of a cue sheet split into tokens and the trivia around them
+///
+/// Concatenating all the parts gives back the original line.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Line {
+    indent: String,
+    keyword: String,
+    spacing: String,
+    args: String,
+    trailing: String,
+    newline: String,
+}
+/// Lines before the first `TRACK` of a `FILE` and the `TRACK` blocks after them
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct FileNode {
+    line: Line,
+    body: Vec<Line>,
+    tracks: Vec<TrackNode>,
+}
+/// A `TRACK` line and all the lines until the next `TRACK` or `FILE`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct TrackNode {
+    line: Line,
+    body: Vec<Line>,
+}
+/// The lossless syntax tree of a whole cue sheet
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct SyntaxTree {
+    bom: bool,
+    header: Vec<Line>,
+    files: Vec<FileNode>,
+}
+
+impl Line {
+    /// Splits a line, including its line ending if any, into tokens
+    ///
+    /// ```rust
+    /// use cuna::parser::cst::Line;
+    ///
+    /// let line = Line::parse("  Title   \"Hello\"  \r\n");
+    /// assert_eq!(line.keyword(), "Title");
+    /// assert_eq!(line.args(), "\"Hello\"");
+    /// assert_eq!(line.value(), "Hello");
+    /// assert_eq!(line.to_string(), "  Title   \"Hello\"  \r\n");
+    /// ```
+    pub fn parse(s: &str) -> Self {
+        let (content, newline) = split_newline(s);
+        let body = content.trim_start();
+        let indent = &content[..content.len() - body.len()];
+        let keyword_len = body.find(char::is_whitespace).unwrap_or(body.len());
+        let (keyword, rest) = body.split_at(keyword_len);
+        let args = rest.trim_start();
+        let spacing = &rest[..rest.len() - args.len()];
+        let trimmed = args.trim_end();
+        Self {
+            indent: indent.to_owned(),
+            keyword: keyword.to_owned(),
+            spacing: spacing.to_owned(),
+            args: trimmed.to_owned(),
+            trailing: args[trimmed.len()..].to_owned(),
+            newline: newline.to_owned(),
+        }
+    }
+    /// Builds a new line in the layout used by [`Cuna`]'s `Display`
+    pub fn new(indent: &str, keyword: &str, value: &str, newline: &str) -> Self {
+        let mut line = Self {
+            indent: indent.to_owned(),
+            keyword: keyword.to_owned(),
+            spacing: " ".to_owned(),
+            newline: newline.to_owned(),
+            ..Self::default()
+        };
+        let quoted = QUOTED.iter().any(|kw| kw.eq_ignore_ascii_case(keyword));
+        line.args = if quoted { quote(value) } else { value.to_owned() };
+        line
+    }
+    /// Returns the keyword as written
+    pub fn keyword(&self) -> &str {
+        &self.keyword
+    }
+    /// Returns `true` if the keyword equals to `keyword`, ignoring ASCII case
+    pub fn is(&self, keyword: &str) -> bool {
+        self.keyword.eq_ignore_ascii_case(keyword)
+    }
+    /// Returns `true` if the line contains nothing but whitespace
+    pub fn is_blank(&self) -> bool {
+        self.keyword.is_empty()
+    }
+    /// Returns the arguments as written, without surrounding whitespace
+    pub fn args(&self) -> &str {
+        &self.args
+    }
+    /// Returns the arguments with the surrounding quotes removed
+    pub fn value(&self) -> &str {
+        unquote(&self.args).unwrap_or(&self.args)
+    }
+    /// Returns the line ending, which is empty for the last line without one
+    pub fn newline(&self) -> &str {
+        &self.newline
+    }
+    /// Returns the line without its line ending
+    pub fn content(&self) -> String {
+        [
+            self.indent.as_str(),
+            &self.keyword,
+            &self.spacing,
+            &self.args,
+            &self.trailing,
+        ]
+        .concat()
+    }
+    /// Replaces the arguments, keeping the original quoting and all the trivia
+    pub fn set_value(&mut self, value: &str) {
+        let quoted = unquote(&self.args).is_some()
+            || (self.args.is_empty() && QUOTED.iter().any(|kw| self.is(kw)));
+        self.args = if quoted { quote(value) } else { value.to_owned() };
+        if self.spacing.is_empty() {
+            self.spacing.push(' ');
+        }
+    }
+}
+impl fmt::Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.content(), self.newline)
+    }
+}
+impl FileNode {
+    /// Returns the `FILE` line
+    pub fn line(&self) -> &Line {
+        &self.line
+    }
+    /// Returns the lines between the `FILE` line and the first `TRACK`
+    pub fn body(&self) -> &Vec<Line> {
+        &self.body
+    }
+    pub fn body_mut(&mut self) -> &mut Vec<Line> {
+        &mut self.body
+    }
+    /// Returns the file name with quotes removed
+    pub fn name(&self) -> &str {
+        super::split_file(self.line.args()).0
+    }
+    /// Renames the file, keeping the file type and the original quoting
+    pub fn set_name(&mut self, name: &str) {
+        let args = self.line.args();
+        let (quoted, format) = match args.strip_prefix('"').and_then(|s| s.split_once('"')) {
+            Some((_, format)) => (true, format),
+            None => match super::split_file(args) {
+                (path, Some(_)) => (false, &args[path.len()..]),
+                (_, None) => (false, ""),
+            },
+        };
+        self.line.args = match quoted {
+            true => format!("{}{}", quote(name), format),
+            false => format!("{}{}", name, format),
+        };
+    }
+    pub fn tracks(&self) -> slice::Iter<'_, TrackNode> {
+        self.tracks.iter()
+    }
+    pub fn tracks_mut(&mut self) -> slice::IterMut<'_, TrackNode> {
+        self.tracks.iter_mut()
+    }
+    fn lines(&self) -> impl Iterator<Item = &Line> {
+        std::iter::once(&self.line)
+            .chain(self.body.iter())
+            .chain(self.tracks.iter().flat_map(TrackNode::lines))
+    }
+    fn last_line_mut(&mut self) -> &mut Line {
+        match self.tracks.last_mut() {
+            Some(track) => track.last_line_mut(),
+            None => self.body.last_mut().unwrap_or(&mut self.line),
+        }
+    }
+}
+impl TrackNode {
+    /// Returns the `TRACK` line
+    pub fn line(&self) -> &Line {
+        &self.line
+    }
+    /// Returns the lines after the `TRACK` line
+    pub fn body(&self) -> &Vec<Line> {
+        &self.body
+    }
+    pub fn body_mut(&mut self) -> &mut Vec<Line> {
+        &mut self.body
+    }
+    /// Returns the first line with the given keyword
+    pub fn get(&self, keyword: &str) -> Option<&Line> {
+        self.body.iter().find(|line| line.is(keyword))
+    }
+    /// Sets the value of the first line with the given keyword
+    ///
+    /// If there is no such line, a new one is inserted before the first `INDEX`,
+    /// with the indentation and line ending of its neighbours
+    pub fn set(&mut self, keyword: &str, value: &str) {
+        let at = self
+            .body
+            .iter()
+            .position(|line| line.is("INDEX") || line.is("POSTGAP"))
+            .unwrap_or_else(|| content_end(&self.body));
+        let indent = match self.body.iter().find(|line| !line.is_blank()) {
+            Some(line) => line.indent.clone(),
+            None => format!("{}  ", self.line.indent),
+        };
+        set_line(&mut self.line, &mut self.body, keyword, value, &indent, at)
+    }
+    /// Removes all the lines with the given keyword and returns how many were removed
+    pub fn remove(&mut self, keyword: &str) -> usize {
+        remove_lines(&mut self.body, keyword)
+    }
+    fn lines(&self) -> impl Iterator<Item = &Line> {
+        std::iter::once(&self.line).chain(self.body.iter())
+    }
+    fn last_line_mut(&mut self) -> &mut Line {
+        self.body.last_mut().unwrap_or(&mut self.line)
+    }
+}
+impl SyntaxTree {
+    /// Builds a syntax tree from an str
+    ///
+    /// This never fails since every line is kept as is, even if it is not a valid command
+    pub fn parse(s: &str) -> Self {
+        let mut tree = Self {
+            bom: s.starts_with(BOM),
+            ..Self::default()
+        };
+        for raw in s.trim_start_matches(BOM).split_inclusive('\n') {
+            let line = Line::parse(raw);
+            if line.is("FILE") {
+                tree.files.push(FileNode {
+                    line,
+                    ..FileNode::default()
+                });
+            } else if line.is("TRACK") && !tree.files.is_empty() {
+                let file = tree.files.last_mut().unwrap();
+                file.tracks.push(TrackNode {
+                    line,
+                    body: Vec::new(),
+                });
+            } else {
+                match tree.files.last_mut() {
+                    Some(file) => match file.tracks.last_mut() {
+                        Some(track) => track.body.push(line),
+                        None => file.body.push(line),
+                    },
+                    None => tree.header.push(line),
+                }
+            }
+        }
+        tree
+    }
+    /// Returns `true` if the input starts with a UTF-8 BOM
+    pub fn has_bom(&self) -> bool {
+        self.bom
+    }
+    /// Returns the lines before the first `FILE`
+    pub fn header(&self) -> &Vec<Line> {
+        &self.header
+    }
+    pub fn header_mut(&mut self) -> &mut Vec<Line> {
+        &mut self.header
+    }
+    pub fn files(&self) -> slice::Iter<'_, FileNode> {
+        self.files.iter()
+    }
+    pub fn files_mut(&mut self) -> slice::IterMut<'_, FileNode> {
+        self.files.iter_mut()
+    }
+    /// An iterator over the `TRACK`s in all the `FILE`s
+    pub fn tracks(&self) -> impl Iterator<Item = &TrackNode> {
+        self.files.iter().flat_map(FileNode::tracks)
+    }
+    /// The mutable version of [`SyntaxTree::tracks()`](SyntaxTree::tracks)
+    pub fn tracks_mut(&mut self) -> impl Iterator<Item = &mut TrackNode> {
+        self.files.iter_mut().flat_map(FileNode::tracks_mut)
+    }
+    /// An iterator over all the lines in order
+    pub fn lines(&self) -> impl Iterator<Item = &Line> {
+        self.header
+            .iter()
+            .chain(self.files.iter().flat_map(FileNode::lines))
+    }
+    /// Returns the first header line with the given keyword
+    pub fn get(&self, keyword: &str) -> Option<&Line> {
+        self.header.iter().find(|line| line.is(keyword))
+    }
+    /// Sets the value of the first header line with the given keyword
+    ///
+    /// If there is no such line, a new one is appended after the last non-blank header line
+    pub fn set(&mut self, keyword: &str, value: &str) {
+        let at = content_end(&self.header);
+        let mut anchor = match self.files.first() {
+            Some(file) => file.line.clone(),
+            None => Line::new("", "", "", "\n"),
+        };
+        set_line(&mut anchor, &mut self.header, keyword, value, "", at)
+    }
+    /// Removes all the header lines with the given keyword and returns how many were removed
+    pub fn remove(&mut self, keyword: &str) -> usize {
+        remove_lines(&mut self.header, keyword)
+    }
+    /// Appends a `TRACK` to the last `FILE`, copying the layout of the previous `TRACK`
+    ///
+    /// Returns `None` if there is no `FILE`
+    pub fn push_track(&mut self, id: u8, format: &str) -> Option<&mut TrackNode> {
+        let indent = match self.tracks().last() {
+            Some(track) => track.line.indent.clone(),
+            None => "  ".to_owned(),
+        };
+        let newline = self.newline().to_owned();
+        let file = self.files.last_mut()?;
+        let last = file.last_line_mut();
+        if last.newline.is_empty() {
+            last.newline = newline.clone();
+        }
+        let args = format!("{:02} {}", id, format);
+        file.tracks.push(TrackNode {
+            line: Line::new(&indent, "TRACK", &args, &newline),
+            body: Vec::new(),
+        });
+        file.tracks.last_mut()
+    }
+    /// Lowers the tree into a [`Cuna`]
+    ///
+    /// Each line is parsed the same way as [`Parna::parse()`](super::Parna::parse)
+    pub fn lower(&self) -> Result<Cuna, Error> {
+        let mut sheet = Cuna::default();
+        for (at, line) in self.lines().enumerate() {
+            let to_error = |e| Error::new(e, at + 1);
+            Command::new(&line.content())
+                .map_err(to_error)?
+                .parse(&mut sheet)
+                .map_err(to_error)?;
+        }
+        Ok(sheet)
+    }
+    /// Returns the most used line ending, or `"\n"` if there is none
+    fn newline(&self) -> &str {
+        let crlf = self.lines().filter(|l| l.newline == "\r\n").count();
+        let lf = self.lines().filter(|l| l.newline == "\n").count();
+        if crlf > lf {
+            "\r\n"
+        } else {
+            "\n"
+        }
+    }
+}
+impl fmt::Display for SyntaxTree {
+    /// Prints the tree, which is byte-identical to the input if nothing was changed
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.bom {
+            write!(f, "{}", BOM)?;
+        }
+        for line in self.lines() {
+            write!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+fn split_newline(s: &str) -> (&str, &str) {
+    if let Some(content) = s.strip_suffix("\r\n") {
+        (content, "\r\n")
+    } else if let Some(content) = s.strip_suffix('\n') {
+        (content, "\n")
+    } else {
+        (s, "")
+    }
+}
+fn quote(s: &str) -> String {
+    format!(r#""{}""#, s)
+}
+fn unquote(s: &str) -> Option<&str> {
+    s.strip_prefix('"').and_then(|s| s.strip_suffix('"'))
+}
+/// Returns the position after the last non-blank line
+fn content_end(lines: &[Line]) -> usize {
+    lines.iter().rposition(|l| !l.is_blank()).map_or(0, |at| at + 1)
+}
+fn set_line(
+    owner: &mut Line,
+    lines: &mut Vec<Line>,
+    keyword: &str,
+    value: &str,
+    indent: &str,
+    at: usize,
+) {
+    if let Some(line) = lines.iter_mut().find(|line| line.is(keyword)) {
+        return line.set_value(value);
+    }
+    let fallback = std::iter::once(&*owner)
+        .chain(lines.iter())
+        .map(Line::newline)
+        .find(|nl| !nl.is_empty())
+        .unwrap_or("\n")
+        .to_owned();
+    let prev = match at {
+        0 => owner,
+        _ => &mut lines[at - 1],
+    };
+    // `prev` was the last line of the sheet, so the new line takes over its missing line ending
+    let newline = if prev.newline.is_empty() {
+        std::mem::replace(&mut prev.newline, fallback)
+    } else {
+        prev.newline.clone()
+    };
+    lines.insert(at, Line::new(indent, keyword, value, &newline));
+}
+fn remove_lines(lines: &mut Vec<Line>, keyword: &str) -> usize {
+    let len = lines.len();
+    lines.retain(|line| !line.is(keyword));
+    len - lines.len()
+}
@@ -0,0 +1,90 @@
+use crate::error::TimeOutOfRange;
+use crate::time::Rounding;
+use crate::time::TimeStamp;
+use std::fmt;
+use std::time::Duration;
+
+/// A position counted in samples at a sample rate
+///
+/// Unlike [`TimeStamp`], it can point inside a CD frame,
+/// which is needed for rates that are not a multiple of 44100 Hz
+///
+/// ```rust
+/// use cuna::sample::SampleTime;
+/// use cuna::time::{Rounding, TimeStamp};
+///
+/// let start = SampleTime::from_timestamp(TimeStamp::new(3, 12, 40), 48000);
+/// assert_eq!(start.samples(), 9241600);
+/// assert_eq!(start.frame_offset(), 0);
+///
+/// let split = SampleTime::new(9241900, 48000);
+/// assert_eq!(split.frame_offset(), 300);
+/// assert_eq!(split.to_timestamp(Rounding::Floor), Ok(TimeStamp::new(3, 12, 40)));
+/// assert_eq!(split.to_rate(44100, Rounding::Nearest).samples(), 8490996);
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct SampleTime {
+    samples: u64,
+    rate: u32,
+}
+
+impl SampleTime {
+    /// # Panics
+    ///
+    /// Panics if `rate` is 0
+    pub const fn new(samples: u64, rate: u32) -> Self {
+        assert!(rate != 0, "sample rate must not be zero");
+        Self { samples, rate }
+    }
+    /// Converts a `TimeStamp` to samples at `rate` Hz, see [`TimeStamp::to_samples`]
+    pub fn from_timestamp(ts: TimeStamp, rate: u32) -> Self {
+        Self::new(ts.to_samples(rate), rate)
+    }
+    pub const fn samples(&self) -> u64 {
+        self.samples
+    }
+    pub const fn rate(&self) -> u32 {
+        self.rate
+    }
+    /// Converts to the frame chosen by `rounding`
+    ///
+    /// Fails if the result has more than `u64::MAX` frames
+    pub fn to_timestamp(&self, rounding: Rounding) -> Result<TimeStamp, TimeOutOfRange> {
+        let frames = rounding.div(self.samples as u128 * 75, self.rate as u128);
+        u64::try_from(frames)
+            .map(TimeStamp::from_frames_u64)
+            .map_err(|_| TimeOutOfRange)
+    }
+    /// Returns the number of samples past the start of the frame containing this position
+    pub fn frame_offset(&self) -> u64 {
+        let frame = self.to_timestamp(Rounding::Floor).unwrap_or(TimeStamp::MAX);
+        self.samples - frame.to_samples(self.rate).min(self.samples)
+    }
+    /// Converts to the sample chosen by `rounding` at another rate
+    ///
+    /// # Panics
+    ///
+    /// Panics if `rate` is 0 or the result does not fit in a `u64`
+    pub fn to_rate(&self, rate: u32, rounding: Rounding) -> Self {
+        let samples = rounding.div(self.samples as u128 * rate as u128, self.rate as u128);
+        let samples = u64::try_from(samples).expect("overflow when converting sample rate");
+        Self::new(samples, rate)
+    }
+    /// Converts to a `Duration`, rounding to the nearest nanosecond
+    pub fn to_duration(&self) -> Duration {
+        let rate = self.rate as u64;
+        let nanos =
+            Rounding::Nearest.div((self.samples % rate) as u128 * 1_000_000_000, rate as u128);
+        Duration::from_secs(self.samples / rate) + Duration::from_nanos(nanos as u64)
+    }
+}
+impl From<SampleTime> for Duration {
+    fn from(st: SampleTime) -> Self {
+        st.to_duration()
+    }
+}
+impl fmt::Display for SampleTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{}Hz", self.samples, self.rate)
+    }
+}
@@ -0,0 +1,17 @@
+use crate::track::Track;
+
+/// Frames between the last track of a session and the first track of the next one
+///
+/// The lead-out of the previous session, the lead-in of the next one and the pregap of its first track
+pub const SESSION_GAP: u32 = 11400;
+
+/// Tracks recorded in one session of a multi-session disc, like the data track of an Enhanced CD
+///
+/// The session changes at `REM SESSION`, which EAC writes after the last track of the previous session,
+/// so the comment applies to the tracks after the one it is scoped in.
+/// A `REM SESSION` before the first `TRACK` of a `FILE` or of the sheet applies from that track
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Session<'a> {
+    pub number: u8,
+    pub tracks: Vec<&'a Track>,
+}
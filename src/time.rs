@@ -1,4 +1,6 @@
 use crate::error::InvalidArgument;
+use crate::error::TimeOutOfRange;
+use crate::sample::SampleTime;
 use crate::utils::number;
 use nom::bytes::complete::tag;
 use nom::character::complete::digit1;
@@ -6,16 +8,68 @@ use nom::combinator::map;
 use nom::sequence::terminated;
 use nom::sequence::tuple;
 use std::fmt;
+use std::iter::Sum;
+use std::ops::Add;
+use std::ops::AddAssign;
+use std::ops::Div;
+use std::ops::Mul;
+use std::ops::Neg;
+use std::ops::Sub;
+use std::ops::SubAssign;
 use std::str::FromStr;
 use std::time::Duration;
 
-#[derive(Debug, PartialEq, Eq, Clone, Default, Hash, Copy)]
+/// A position or length in minutes, seconds and frames, where a second has 75 frames
+///
+/// Arithmetic works on frames exactly.
+/// Like integers, operators panic on overflow or underflow,
+/// use `checked_*()` or `saturating_*()` to handle them
+///
+/// ```rust
+/// use cuna::time::TimeStamp;
+///
+/// let start = TimeStamp::new(4, 15, 44);
+/// let end = TimeStamp::new(8, 4, 33);
+/// assert!(start < end);
+/// assert_eq!(end - start, TimeStamp::new(3, 48, 64));
+/// assert_eq!(start.checked_sub(end), None);
+/// assert_eq!((end - start) * 2, TimeStamp::new(7, 37, 53));
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Default, Hash, Copy, PartialOrd, Ord)]
 pub struct TimeStamp {
-    seconds: u32,
-    frames: u8,
+    frames: u64,
+}
+/// A signed difference between two [`TimeStamp`]s in frames
+///
+/// ```rust
+/// use cuna::time::{TimeOffset, TimeStamp};
+///
+/// let pregap = TimeStamp::new(0, 2, 0).diff(TimeStamp::new(0, 4, 0));
+/// assert_eq!(pregap, TimeOffset::from_frames(-150));
+/// assert_eq!(pregap.to_string(), "-00:02:00");
+/// assert_eq!(TimeStamp::new(0, 4, 0) + pregap, TimeStamp::new(0, 2, 0));
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Default, Hash, Copy, PartialOrd, Ord)]
+pub struct TimeOffset {
+    frames: i64,
+}
+/// How to round a time which falls between two frames
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Default)]
+pub enum Rounding {
+    /// Rounds down to the previous frame
+    Floor,
+    /// Rounds to the closest frame, ties go up
+    #[default]
+    Nearest,
+    /// Rounds up to the next frame
+    Ceil,
 }
 
 impl TimeStamp {
+    /// The largest `TimeStamp`
+    pub const MAX: Self = Self::from_frames_u64(u64::MAX);
+    pub const ZERO: Self = Self::from_frames_u64(0);
+
     /// Constructs a new TimeStamp with minutes, seconds and frames
     ///
     /// # Panics
@@ -29,10 +83,7 @@ impl TimeStamp {
         if seconds >= 60 || frames >= 75 {
             None
         } else {
-            Some(Self {
-                seconds: minutes * 60 + seconds,
-                frames: frames as u8,
-            })
+            Some(Self::from_msf(minutes, seconds, frames))
         }
     }
     /// Constructs a new TimeStamp with minutes, seconds and frames
@@ -44,13 +95,8 @@ impl TimeStamp {
     /// let ts = TimeStamp::from_msf(11, 61, 78);
     /// assert_eq!(ts, TimeStamp::new(12, 2, 3));
     /// ```
-    /// Make sure `minutes * 60 + seconds + (frames / 75)` will not be larger than u32::MAX
-    /// or overflow will happen
     pub const fn from_msf(minutes: u32, seconds: u32, frames: u32) -> Self {
-        Self {
-            seconds: minutes * 60 + seconds + (frames / 75),
-            frames: (frames % 75) as u8,
-        }
+        Self::from_frames_u64((minutes as u64 * 60 + seconds as u64) * 75 + frames as u64)
     }
     /// Constructs a new TimeStamp with frames
     /// ``` rust
@@ -59,42 +105,69 @@ impl TimeStamp {
     /// assert_eq!(ts, TimeStamp::new(12, 2, 3));
     /// ```
     pub const fn from_frames(frms: u32) -> Self {
-        Self::from_msf(0, 0, frms)
+        Self::from_frames_u64(frms as u64)
     }
+    /// Like [`from_frames()`](TimeStamp::from_frames), but takes 64-bit frames
+    pub const fn from_frames_u64(frms: u64) -> Self {
+        Self { frames: frms }
+    }
+    /// # Panics
+    ///
+    /// Panics if the minutes do not fit in a `u32`, see [`minutes_u64()`](TimeStamp::minutes_u64)
     pub const fn minutes(&self) -> u32 {
-        self.seconds / 60
+        to_u32(self.minutes_u64())
+    }
+    pub const fn minutes_u64(&self) -> u64 {
+        self.total_seconds_u64() / 60
     }
     pub const fn seconds(&self) -> u32 {
-        self.seconds % 60
+        (self.total_seconds_u64() % 60) as u32
     }
     pub const fn frames(&self) -> u32 {
-        self.frames as u32
+        (self.frames % 75) as u32
     }
     pub fn set_minutes(&mut self, minutes: u32) {
-        self.seconds = self.seconds() + minutes * 60;
+        self.set_minutes_u64(minutes as u64);
+    }
+    /// # Panics
+    ///
+    /// Panics if the result does not fit in 64-bit frames
+    pub fn set_minutes_u64(&mut self, minutes: u64) {
+        self.frames = minutes
+            .checked_mul(60 * 75)
+            .and_then(|f| f.checked_add(self.frames % (60 * 75)))
+            .expect("overflow when setting minutes");
     }
     /// # Panics
     ///
     /// Panics if seconds >= 60
     pub fn set_seconds(&mut self, seconds: u32) {
         assert!(seconds < 60);
-        self.seconds = self.minutes() * 60 + seconds;
+        self.frames = (self.minutes_u64() * 60 + seconds as u64) * 75 + self.frames() as u64;
     }
     /// # Panics
     ///
     /// Panics if frames >= 75
     pub fn set_frames(&mut self, frames: u32) {
         assert!(frames < 75);
-        self.frames = frames as u8;
+        self.frames = self.total_seconds_u64() * 75 + frames as u64;
     }
     #[deprecated = "use total_seconds() instead"]
     /// Returns the total number of whole seconds contained by this `TimeStamp`
     pub const fn as_seconds(&self) -> u32 {
-        self.seconds
+        self.total_seconds()
     }
     /// Returns the total number of whole seconds contained by this `TimeStamp`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the result does not fit in a `u32`, see [`total_seconds_u64()`](TimeStamp::total_seconds_u64)
     pub const fn total_seconds(&self) -> u32 {
-        self.seconds
+        to_u32(self.total_seconds_u64())
+    }
+    /// Returns the total number of whole seconds contained by this `TimeStamp`
+    pub const fn total_seconds_u64(&self) -> u64 {
+        self.frames / 75
     }
     #[deprecated = "use total_frames() instead"]
     /// Returns the total number of whole frames contained by this `TimeStamp`
@@ -103,9 +176,290 @@ impl TimeStamp {
     }
     /// Returns the total number of whole frames contained by this `TimeStamp`
     ///
-    /// Overflows if `self.total_seconds() * 75 + self.frames() > u32::MAX`
+    /// # Panics
+    ///
+    /// Panics if the result does not fit in a `u32`, see [`total_frames_u64()`](TimeStamp::total_frames_u64)
     pub const fn total_frames(&self) -> u32 {
-        self.total_seconds() * 75 + self.frames()
+        to_u32(self.frames)
+    }
+    /// Returns the total number of whole frames contained by this `TimeStamp`
+    pub const fn total_frames_u64(&self) -> u64 {
+        self.frames
+    }
+    pub const fn is_zero(&self) -> bool {
+        self.frames == 0
+    }
+    /// Converts a `Duration` to the frame chosen by `rounding`
+    ///
+    /// Fails if the duration has more than `u64::MAX` frames
+    ///
+    /// ``` rust
+    /// use cuna::time::{Rounding, TimeStamp};
+    /// use std::time::Duration;
+    ///
+    /// let chapter = Duration::from_millis(83_995);
+    /// assert_eq!(TimeStamp::from_duration_rounded(chapter, Rounding::Floor), Ok(TimeStamp::new(1, 23, 74)));
+    /// assert_eq!(TimeStamp::from_duration_rounded(chapter, Rounding::Nearest), Ok(TimeStamp::new(1, 24, 0)));
+    ///
+    /// let ts = TimeStamp::new(1, 23, 74);
+    /// assert_eq!(TimeStamp::from_duration_rounded(ts.to_duration(), Rounding::Nearest), Ok(ts));
+    /// ```
+    pub fn from_duration_rounded(d: Duration, rounding: Rounding) -> Result<Self, TimeOutOfRange> {
+        let frames = rounding.div(d.as_nanos() * 75, 1_000_000_000);
+        u64::try_from(frames)
+            .map(Self::from_frames_u64)
+            .map_err(|_| TimeOutOfRange)
+    }
+    /// Constructs a new TimeStamp with a sample count at `rate` Hz
+    ///
+    /// Fails if the result has more than `u64::MAX` frames
+    ///
+    /// # Panics
+    ///
+    /// Panics if `rate` is 0
+    ///
+    /// ``` rust
+    /// use cuna::time::{Rounding, TimeStamp};
+    ///
+    /// assert_eq!(TimeStamp::from_samples(588, 44100, Rounding::Floor), Ok(TimeStamp::from_frames(1)));
+    /// assert_eq!(TimeStamp::from_samples(700, 48000, Rounding::Floor), Ok(TimeStamp::from_frames(1)));
+    /// assert_eq!(TimeStamp::from_samples(700, 48000, Rounding::Ceil), Ok(TimeStamp::from_frames(2)));
+    /// ```
+    pub fn from_samples(
+        samples: u64,
+        rate: u32,
+        rounding: Rounding,
+    ) -> Result<Self, TimeOutOfRange> {
+        SampleTime::new(samples, rate).to_timestamp(rounding)
+    }
+    /// Returns the number of samples at `rate` Hz, rounded to the nearest sample
+    ///
+    /// The result is exact if `rate` is a multiple of 75, such as 44100, 48000 and 96000
+    ///
+    /// # Panics
+    ///
+    /// Panics if the result does not fit in a `u64`
+    pub fn to_samples(&self, rate: u32) -> u64 {
+        self.checked_to_samples(rate)
+            .expect("overflow when converting timestamp to samples")
+    }
+    /// Returns the number of samples at `rate` Hz, or `None` if it does not fit in a `u64`
+    pub fn checked_to_samples(&self, rate: u32) -> Option<u64> {
+        let samples = Rounding::Nearest.div(self.frames as u128 * rate as u128, 75);
+        u64::try_from(samples).ok()
+    }
+    /// Converts to a `Duration`, rounding to the nearest nanosecond
+    pub const fn to_duration(&self) -> Duration {
+        let nanos = (self.frames() as u64 * 1_000_000_000 + 37) / 75;
+        Duration::new(self.total_seconds_u64(), nanos as u32)
+    }
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        self.frames.checked_add(rhs.frames).map(Self::from_frames_u64)
+    }
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+        self.frames.checked_sub(rhs.frames).map(Self::from_frames_u64)
+    }
+    pub fn checked_mul(self, rhs: u32) -> Option<Self> {
+        self.frames.checked_mul(rhs as u64).map(Self::from_frames_u64)
+    }
+    /// Divides by `rhs` and rounds down to a whole frame
+    pub fn checked_div(self, rhs: u32) -> Option<Self> {
+        self.frames.checked_div(rhs as u64).map(Self::from_frames_u64)
+    }
+    pub fn saturating_add(self, rhs: Self) -> Self {
+        Self::from_frames_u64(self.frames.saturating_add(rhs.frames))
+    }
+    pub fn saturating_sub(self, rhs: Self) -> Self {
+        Self::from_frames_u64(self.frames.saturating_sub(rhs.frames))
+    }
+    pub fn saturating_mul(self, rhs: u32) -> Self {
+        Self::from_frames_u64(self.frames.saturating_mul(rhs as u64))
+    }
+    /// Returns `self - rhs`, which is negative if `rhs` is later
+    ///
+    /// # Panics
+    ///
+    /// Panics if the difference does not fit in an `i64`
+    pub fn diff(self, rhs: Self) -> TimeOffset {
+        self.checked_diff(rhs)
+            .expect("overflow when subtracting timestamps")
+    }
+    /// Returns `self - rhs`, or `None` if the difference does not fit in an `i64`
+    pub fn checked_diff(self, rhs: Self) -> Option<TimeOffset> {
+        let frames = self.frames as i128 - rhs.frames as i128;
+        i64::try_from(frames).ok().map(TimeOffset::from_frames)
+    }
+    /// Adds a signed offset, returns `None` if the result is negative or overflows
+    pub fn checked_add_offset(self, rhs: TimeOffset) -> Option<Self> {
+        if rhs.is_negative() {
+            self.frames.checked_sub(rhs.frames.unsigned_abs())
+        } else {
+            self.frames.checked_add(rhs.frames as u64)
+        }
+        .map(Self::from_frames_u64)
+    }
+}
+impl Rounding {
+    pub(crate) fn div(self, n: u128, d: u128) -> u128 {
+        match self {
+            Rounding::Floor => n / d,
+            Rounding::Nearest => (n + d / 2) / d,
+            Rounding::Ceil => n.div_ceil(d),
+        }
+    }
+}
+/// Narrows a count for the 32-bit accessors of `TimeStamp`
+const fn to_u32(n: u64) -> u32 {
+    if n > u32::MAX as u64 {
+        panic!("overflow when converting timestamp to u32");
+    }
+    n as u32
+}
+impl Add for TimeStamp {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        self.checked_add(rhs)
+            .expect("overflow when adding timestamps")
+    }
+}
+impl Sub for TimeStamp {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.checked_sub(rhs)
+            .expect("overflow when subtracting timestamps")
+    }
+}
+impl AddAssign for TimeStamp {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+impl SubAssign for TimeStamp {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+impl Mul<u32> for TimeStamp {
+    type Output = Self;
+
+    fn mul(self, rhs: u32) -> Self::Output {
+        self.checked_mul(rhs)
+            .expect("overflow when multiplying timestamp by scalar")
+    }
+}
+impl Div<u32> for TimeStamp {
+    type Output = Self;
+
+    /// Rounds down to a whole frame
+    fn div(self, rhs: u32) -> Self::Output {
+        self.checked_div(rhs)
+            .expect("divide by zero error when dividing timestamp by scalar")
+    }
+}
+impl Add<TimeOffset> for TimeStamp {
+    type Output = Self;
+
+    fn add(self, rhs: TimeOffset) -> Self::Output {
+        self.checked_add_offset(rhs)
+            .expect("overflow when adding offset to timestamp")
+    }
+}
+impl Sub<TimeOffset> for TimeStamp {
+    type Output = Self;
+
+    fn sub(self, rhs: TimeOffset) -> Self::Output {
+        self + -rhs
+    }
+}
+impl Sum for TimeStamp {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::ZERO, Add::add)
+    }
+}
+impl<'a> Sum<&'a TimeStamp> for TimeStamp {
+    fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        iter.copied().sum()
+    }
+}
+impl TimeOffset {
+    pub const ZERO: Self = Self::from_frames(0);
+
+    pub const fn from_frames(frames: i64) -> Self {
+        Self { frames }
+    }
+    pub const fn total_frames(&self) -> i64 {
+        self.frames
+    }
+    pub const fn is_negative(&self) -> bool {
+        self.frames < 0
+    }
+    /// Returns the absolute value as a `TimeStamp`
+    pub const fn abs(&self) -> TimeStamp {
+        TimeStamp::from_frames_u64(self.frames.unsigned_abs())
+    }
+    /// Converts to a `TimeStamp`, returns `None` if negative
+    pub fn to_timestamp(&self) -> Option<TimeStamp> {
+        u64::try_from(self.frames).ok().map(TimeStamp::from_frames_u64)
+    }
+}
+impl TryFrom<TimeStamp> for TimeOffset {
+    type Error = TimeOutOfRange;
+
+    fn try_from(ts: TimeStamp) -> Result<Self, Self::Error> {
+        i64::try_from(ts.frames)
+            .map(Self::from_frames)
+            .map_err(|_| TimeOutOfRange)
+    }
+}
+impl Neg for TimeOffset {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Self::from_frames(-self.frames)
+    }
+}
+impl Add for TimeOffset {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self::from_frames(self.frames + rhs.frames)
+    }
+}
+impl Sub for TimeOffset {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self::from_frames(self.frames - rhs.frames)
+    }
+}
+impl Mul<i64> for TimeOffset {
+    type Output = Self;
+
+    fn mul(self, rhs: i64) -> Self::Output {
+        Self::from_frames(self.frames * rhs)
+    }
+}
+impl Div<i64> for TimeOffset {
+    type Output = Self;
+
+    /// Rounds toward zero to a whole frame
+    fn div(self, rhs: i64) -> Self::Output {
+        Self::from_frames(self.frames / rhs)
+    }
+}
+impl Sum for TimeOffset {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::ZERO, Add::add)
+    }
+}
+impl fmt::Display for TimeOffset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_negative() {
+            write!(f, "-")?;
+        }
+        write!(f, "{}", self.abs())
     }
 }
 impl FromStr for TimeStamp {
@@ -126,7 +480,7 @@ impl fmt::Display for TimeStamp {
         write!(
             f,
             "{:0>2}:{:0>2}:{:0>2}",
-            self.minutes(),
+            self.minutes_u64(),
             self.seconds(),
             self.frames()
         )
@@ -134,7 +488,7 @@ impl fmt::Display for TimeStamp {
 }
 impl From<&TimeStamp> for Duration {
     fn from(ti: &TimeStamp) -> Duration {
-        Duration::from_secs(ti.seconds as u64) + Duration::from_millis(ti.frames() as u64 * 40 / 3)
+        ti.to_duration()
     }
 }
 impl From<&mut TimeStamp> for Duration {
@@ -147,20 +501,25 @@ impl From<TimeStamp> for Duration {
         Duration::from(&ti)
     }
 }
-impl From<&Duration> for TimeStamp {
-    fn from(dr: &Duration) -> Self {
-        let secs = dr.as_secs();
-        let frames = dr.subsec_millis() * 3 / 40;
-        Self::from_msf(0, secs as u32, frames)
+impl TryFrom<&Duration> for TimeStamp {
+    type Error = TimeOutOfRange;
+
+    /// Rounds down to a whole frame, see [`TimeStamp::from_duration_rounded`]
+    fn try_from(dr: &Duration) -> Result<Self, Self::Error> {
+        TimeStamp::from_duration_rounded(*dr, Rounding::Floor)
     }
 }
-impl From<&mut Duration> for TimeStamp {
-    fn from(dr: &mut Duration) -> TimeStamp {
-        TimeStamp::from(&*dr)
+impl TryFrom<&mut Duration> for TimeStamp {
+    type Error = TimeOutOfRange;
+
+    fn try_from(dr: &mut Duration) -> Result<Self, Self::Error> {
+        TimeStamp::try_from(&*dr)
     }
 }
-impl From<Duration> for TimeStamp {
-    fn from(dr: Duration) -> TimeStamp {
-        TimeStamp::from(&dr)
+impl TryFrom<Duration> for TimeStamp {
+    type Error = TimeOutOfRange;
+
+    fn try_from(dr: Duration) -> Result<Self, Self::Error> {
+        TimeStamp::try_from(&dr)
     }
 }
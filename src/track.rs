@@ -1,4 +1,14 @@
+use crate::address::Lba;
+use crate::comment::Comment;
+use crate::comment::RemDate;
 use crate::error::InvalidArgument;
+use crate::file_type::FileType;
+use crate::flag::Flag;
+use crate::flag::Flags;
+use crate::isrc::Isrc;
+use crate::mode::TrackMode;
+use crate::parser::Command;
+use crate::time::TimeOffset;
 use crate::time::TimeStamp;
 use crate::utils;
 use nom::bytes::complete::tag_no_case as tag;
@@ -7,6 +17,7 @@ use nom::combinator::map_res;
 use nom::combinator::rest;
 use nom::sequence::delimited;
 use nom::sequence::tuple;
+use std::fmt;
 use std::ops;
 use std::str::FromStr;
 
@@ -21,7 +32,7 @@ pub struct Index {
 #[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
 pub struct Track {
     pub(crate) id: u8, // track-id must between 1 and 99
-    pub format: String,
+    pub format: TrackMode,
     pub index: Vec<Index>,
     pub pregap: Option<TimeStamp>,
     pub postgap: Option<TimeStamp>,
@@ -29,13 +40,20 @@ pub struct Track {
     pub performer: Vec<String>,
     pub songwriter: Vec<String>,
     pub isrc: Option<String>,
-    pub flags: Vec<String>,
+    pub flags: Flags,
+    /// `REM` comments inside the `TRACK` scope
+    pub comments: Comment,
 }
 #[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
 pub struct Disc {
     pub name: String,
-    pub format: String,
+    pub format: FileType,
     pub tracks: Vec<Track>,
+    /// `REM` comments between `FILE` and its first `TRACK`
+    pub comments: Comment,
+    /// `INDEX` commands between `FILE` and its first `TRACK`,
+    /// which continue the last track of the previous `FILE` as written by EAC
+    pub continued: Vec<Index>,
 }
 
 impl Index {
@@ -58,6 +76,24 @@ impl Index {
     pub fn begin_time(&self) -> &TimeStamp {
         &self.begin_time
     }
+    /// Returns the LBA of this index in a file starting at `file_start`
+    ///
+    /// For a single-file image, `file_start` is `Lba(0)`
+    ///
+    /// ```rust
+    /// use cuna::address::{AbsoluteMsf, Lba};
+    /// use cuna::time::TimeStamp;
+    /// use cuna::track::Index;
+    ///
+    /// let index = Index::new(1, TimeStamp::new(3, 12, 40));
+    /// let lba = index.lba(Lba(0)).unwrap();
+    /// assert_eq!(lba, Lba(14440));
+    /// assert_eq!(lba.to_msf(), Some(AbsoluteMsf::new(3, 14, 40)));
+    /// ```
+    pub fn lba(&self, file_start: Lba) -> Option<Lba> {
+        let offset = TimeOffset::try_from(self.begin_time).ok()?;
+        file_start.checked_add(offset)
+    }
 }
 impl FromStr for Index {
     type Err = InvalidArgument;
@@ -74,8 +110,13 @@ impl FromStr for Index {
         Ok(index)
     }
 }
+impl fmt::Display for Index {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Command::Index(self.id, self.begin_time))
+    }
+}
 impl Track {
-    pub(crate) fn new_unchecked(id: u8, format: String) -> Self {
+    pub(crate) fn new_unchecked(id: u8, format: TrackMode) -> Self {
         Self {
             id,
             format,
@@ -87,10 +128,10 @@ impl Track {
     /// # Panics
     ///
     /// Panics if id > 99
-    pub fn new(id: u8, format: String) -> Self {
+    pub fn new(id: u8, format: TrackMode) -> Self {
         Self::new_opt(id, format).expect("track-id must be between 1 and 99")
     }
-    pub fn new_opt(id: u8, format: String) -> Option<Self> {
+    pub fn new_opt(id: u8, format: TrackMode) -> Option<Self> {
         if id <= 99 {
             Some(Self::new_unchecked(id, format))
         } else {
@@ -100,7 +141,7 @@ impl Track {
     pub fn id(&self) -> u8 {
         self.id
     }
-    pub fn format(&self) -> &str {
+    pub fn format(&self) -> &TrackMode {
         &self.format
     }
     pub fn index(&self) -> &Vec<Index> {
@@ -149,18 +190,50 @@ impl Track {
     pub fn set_isrc(&mut self, isrc: String) -> Option<String> {
         self.isrc.replace(isrc)
     }
-    pub fn flags(&self) -> &Vec<String> {
+    /// Parses the ISRC of the track, returns `Ok(None)` if there is no `ISRC` command
+    ///
+    /// ```rust
+    /// use cuna::Cuna;
+    ///
+    /// let cue = Cuna::new("FILE \"a.wav\" WAVE\nTRACK 01 AUDIO\nISRC JPI101200600").unwrap();
+    /// assert_eq!(cue[0][0].isrc_code().unwrap().unwrap().country(), "JP");
+    /// ```
+    pub fn isrc_code(&self) -> Result<Option<Isrc>, InvalidArgument> {
+        self.isrc().map(str::parse).transpose()
+    }
+    pub fn flags(&self) -> &Flags {
         &self.flags
     }
-    pub fn push_flag(&mut self, flag: String) {
-        self.flags.push(flag)
+    pub fn set_flags(&mut self, flags: Flags) -> Flags {
+        std::mem::replace(&mut self.flags, flags)
     }
-    pub fn push_flags<F, S>(&mut self, flags: F)
-    where
-        F: IntoIterator<Item = S>,
-        S: Into<String>,
-    {
-        self.flags.extend(flags.into_iter().map(Into::into))
+    pub fn push_flag(&mut self, flag: Flag) {
+        self.flags.insert(flag)
+    }
+    pub fn push_flags<F: IntoIterator<Item = Flag>>(&mut self, flags: F) {
+        self.flags.extend(flags)
+    }
+    pub fn comments(&self) -> &Comment {
+        &self.comments
+    }
+    pub fn push_comment(&mut self, comment: String) {
+        self.comments.push(comment)
+    }
+    /// Returns the value of `REM COMPOSER` of this track
+    pub fn composer(&self) -> Option<&str> {
+        self.comments.composer()
+    }
+    /// Returns the value of `REM GENRE` of this track
+    pub fn genre(&self) -> Option<&str> {
+        self.comments.genre()
+    }
+    /// Returns the value of `REM COMMENT` of this track
+    pub fn comment(&self) -> Option<&str> {
+        self.comments.comment()
+    }
+    /// Returns the value of `REM DATE` of this track
+    pub fn date(&self) -> Option<RemDate> {
+        self.comments.date()
     }
 }
 impl FromStr for Track {
@@ -169,7 +242,41 @@ impl FromStr for Track {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let (tp, id) = delimited(utils::keyword("TRACK"), utils::number(2), tag(" "))(s)
             .map_err(|_| InvalidArgument::InvalidId)?;
-        Ok(Self::new_unchecked(id, tp.to_owned()))
+        Ok(Self::new_unchecked(id, TrackMode::from(tp)))
+    }
+}
+impl fmt::Display for Track {
+    /// Writes the `TRACK` block with the indentation used by most rippers
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "  {}", Command::Track(self.id, self.format.as_str()))?;
+        if !self.flags.is_empty() {
+            writeln!(f, "    {}", Command::Flags(self.flags.clone()))?;
+        }
+        for title in self.title() {
+            writeln!(f, "    {}", Command::Title(title))?;
+        }
+        for performer in self.performer() {
+            writeln!(f, "    {}", Command::Performer(performer))?;
+        }
+        for songwriter in self.songwriter() {
+            writeln!(f, "    {}", Command::Songwriter(songwriter))?;
+        }
+        for comment in self.comments() {
+            writeln!(f, "    {}", Command::Rem(comment))?;
+        }
+        if let Some(isrc) = self.isrc() {
+            writeln!(f, "    {}", Command::Isrc(isrc.into()))?;
+        }
+        if let Some(pregap) = self.pregap() {
+            writeln!(f, "    {}", Command::Pregap(&pregap.to_string()))?;
+        }
+        for index in self.index() {
+            writeln!(f, "    {}", index)?;
+        }
+        if let Some(postgap) = self.postgap() {
+            writeln!(f, "    {}", Command::Postgap(&postgap.to_string()))?;
+        }
+        Ok(())
     }
 }
 impl ops::Index<usize> for Track {
@@ -181,14 +288,16 @@ impl ops::Index<usize> for Track {
 }
 impl Disc {
     /// Constructs a new TrackInfo
-    pub const fn new(name: String, format: String) -> Self {
+    pub const fn new(name: String, format: FileType) -> Self {
         Self::with_tracks(name, format, Vec::new())
     }
-    pub const fn with_tracks(name: String, format: String, tracks: Vec<Track>) -> Self {
+    pub const fn with_tracks(name: String, format: FileType, tracks: Vec<Track>) -> Self {
         Self {
             name,
             format,
             tracks,
+            comments: Comment(Vec::new()),
+            continued: Vec::new(),
         }
     }
     pub fn tracks(&self) -> &Vec<Track> {
@@ -212,6 +321,31 @@ impl Disc {
     pub fn push_track(&mut self, track: Track) {
         self.tracks.push(track)
     }
+    pub fn comments(&self) -> &Comment {
+        &self.comments
+    }
+    pub fn push_comment(&mut self, comment: String) {
+        self.comments.push(comment)
+    }
+    pub fn continued(&self) -> &Vec<Index> {
+        &self.continued
+    }
+}
+impl fmt::Display for Disc {
+    /// Writes the `FILE` command followed by all of its `TRACK` blocks
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", Command::File(&self.name, self.format.clone()))?;
+        for comment in self.comments() {
+            writeln!(f, "  {}", Command::Rem(comment))?;
+        }
+        for index in self.continued() {
+            writeln!(f, "    {}", index)?;
+        }
+        for track in self.tracks() {
+            write!(f, "{}", track)?;
+        }
+        Ok(())
+    }
 }
 impl IntoIterator for Disc {
     type Item = Track;
@@ -1,10 +1,31 @@
+// `time::create` clones a `Copy` timestamp on purpose
+#![allow(clippy::clone_on_copy)]
+
 type Result = std::result::Result<(), cuna::error::Error>;
 
 const CUE: &str = include_str!(r"EGOIST - Departures ～あなたにおくるアイの歌～.cue");
 
+/// A CD-DA WAVE header with a chunk to skip before the data of `samples` stereo samples
+fn wav_header(samples: u32) -> Vec<u8> {
+    let mut wav = b"RIFF\0\0\0\0WAVEfmt \x10\0\0\0".to_vec();
+    wav.extend([1, 0, 2, 0, 0x44, 0xac, 0, 0, 0x10, 0xb1, 2, 0, 4, 0, 16, 0]);
+    wav.extend(b"LIST\x03\0\0\0abc\0");
+    wav.extend(b"data");
+    wav.extend((samples * 4).to_le_bytes());
+    wav
+}
+/// A CD-DA AIFF header up to the `COMM` chunk of `samples` stereo samples
+fn aiff_header(samples: u32) -> Vec<u8> {
+    let mut aiff = b"FORM\0\0\0\0AIFFCOMM\0\0\0\x12\0\x02".to_vec();
+    aiff.extend(samples.to_be_bytes());
+    aiff.extend([0, 16, 0x40, 0x0e, 0xac, 0x44, 0, 0, 0, 0, 0, 0]);
+    aiff
+}
+
 #[cfg(test)]
 mod time {
     use super::*;
+    use cuna::error::TimeOutOfRange;
     use cuna::time::*;
     use std::time::Duration;
     #[test]
@@ -51,7 +72,49 @@ mod time {
             Duration::from_millis(400 + 63 * 1000)
         );
         let duration = Duration::from_millis(400 + 63 * 1000);
-        assert_eq!(TimeStamp::from(duration), TimeStamp::new(1, 3, 30));
+        assert_eq!(TimeStamp::try_from(duration), Ok(TimeStamp::new(1, 3, 30)));
+        assert_eq!(
+            TimeStamp::try_from(Duration::from_secs(u64::MAX)),
+            Err(TimeOutOfRange)
+        );
+    }
+    #[test]
+    fn rounding() {
+        for frames in 0..150 {
+            let timestamp = TimeStamp::from_frames(frames);
+            let duration = timestamp.to_duration();
+            assert_eq!(
+                TimeStamp::from_duration_rounded(duration, Rounding::Nearest),
+                Ok(timestamp)
+            );
+        }
+        let duration = Duration::from_millis(1);
+        assert_eq!(
+            TimeStamp::from_duration_rounded(duration, Rounding::Floor),
+            Ok(TimeStamp::ZERO)
+        );
+        assert_eq!(
+            TimeStamp::from_duration_rounded(duration, Rounding::Ceil),
+            Ok(TimeStamp::from_frames(1))
+        );
+        assert_eq!(
+            TimeStamp::new(0, 0, 1).to_duration(),
+            Duration::from_nanos(13_333_333)
+        );
+    }
+    #[test]
+    fn long() {
+        let timestamp = TimeStamp::from_msf(u32::MAX, 59, 30);
+        assert_eq!(timestamp.minutes(), u32::MAX);
+        assert_eq!(timestamp.minutes_u64(), u32::MAX as u64);
+        assert_eq!(
+            timestamp.total_frames_u64(),
+            (u32::MAX as u64 * 60 + 59) * 75 + 30
+        );
+        assert!(std::panic::catch_unwind(|| timestamp.total_frames()).is_err());
+        assert_eq!(timestamp.to_string(), "4294967295:59:30");
+        let duration = Duration::from(timestamp);
+        assert_eq!(TimeStamp::try_from(duration), Ok(timestamp));
     }
     #[test]
     fn getter() {
@@ -62,6 +125,24 @@ mod time {
         assert_eq!(timestamp.total_seconds(), 1289);
         assert_eq!(timestamp.total_frames(), 96748);
     }
+    #[test]
+    fn arithmetic() {
+        let a = TimeStamp::new(1, 59, 74);
+        let b = TimeStamp::new(0, 0, 1);
+        assert_eq!(a + b, TimeStamp::new(2, 0, 0));
+        assert_eq!(a - a, TimeStamp::ZERO);
+        assert_eq!(b.checked_sub(a), None);
+        assert_eq!(b.saturating_sub(a), TimeStamp::ZERO);
+        assert_eq!(TimeStamp::MAX.checked_add(b), None);
+        assert_eq!(TimeStamp::new(0, 1, 0) / 2, TimeStamp::new(0, 0, 37));
+        assert_eq!([a, b].iter().sum::<TimeStamp>(), TimeStamp::new(2, 0, 0));
+        let offset = b.diff(a);
+        assert_eq!(offset.total_frames(), -8998);
+        assert_eq!(a + offset, b);
+        assert_eq!(b - offset, a);
+        assert_eq!(offset.abs(), a - b);
+        assert!(b < a);
+    }
 }
 #[cfg(test)]
 mod command {
@@ -143,6 +224,81 @@ mod cue_sheet {
         assert_eq!(track[0], "INDEX 01 08:04:33".parse()?);
         Ok(())
     }
+    #[test]
+    fn diagnostics() -> Result {
+        use cuna::error::Severity;
+
+        let bad = CUE
+            .replace("INDEX 01 04:15:44", "INDEX 01 04:75:44")
+            .replace("TRACK 05 AUDIO", "TRACK 05 AUDIO\n    FOO bar")
+            .replace(r#"TITLE "エウテルペ -Instrumental-""#, "TITLE");
+        let (sheet, diagnostics) = CueSheet::from_buf_read_with_diagnostics(bad.as_bytes())?;
+        let found: Vec<_> = diagnostics
+            .iter()
+            .map(|d| (d.line(), d.severity()))
+            .collect();
+        assert_eq!(
+            found,
+            [(16, Severity::Error), (26, Severity::Warning), (31, Severity::Error)]
+        );
+        assert_eq!(CueSheet::new_with_diagnostics(&bad), (sheet.clone(), diagnostics));
+        assert_eq!(sheet, CueSheet::new_suc(&bad));
+        assert_eq!(sheet.tracks().count(), 7);
+        Ok(())
+    }
+    #[test]
+    fn display() -> Result {
+        for cue in [CUE, include_str!("../benches/LACM-34919.cue")] {
+            let sheet = CueSheet::from_str(cue)?;
+            assert_eq!(CueSheet::new(&sheet.to_string())?, sheet);
+        }
+        let full = r#"REM COMMENT ExactAudioCopy v0.99pb5
+CATALOG 4540774409198
+CDTEXTFILE "cdtext.cdt"
+PERFORMER "ChouCho"
+TITLE "優しさの理由"
+SONGWRITER "ChouCho"
+FILE "LACM-34919.bin" BINARY
+  REM FILE-SCOPE
+  TRACK 01 AUDIO
+    FLAGS DCP PRE
+    TITLE "優しさの理由"
+    PERFORMER "ChouCho"
+    REM COMPOSER "ChouCho"
+    REM REPLAYGAIN_TRACK_GAIN -7.89 dB
+    ISRC JPI101200600
+    PREGAP 00:02:00
+    INDEX 01 00:00:00
+    INDEX 02 01:00:00
+    POSTGAP 00:01:00
+"#;
+        let sheet = CueSheet::new(full)?;
+        assert_eq!(sheet.to_string(), full);
+        assert_eq!(sheet.comments.0, ["COMMENT ExactAudioCopy v0.99pb5"]);
+        assert_eq!(sheet[0].comments.0, ["FILE-SCOPE"]);
+        assert_eq!(sheet[0][0].composer(), Some("ChouCho"));
+        assert_eq!(
+            sheet[0][0].comments.get("REPLAYGAIN_TRACK_GAIN"),
+            Some("-7.89 dB")
+        );
+        let mut buf = Vec::new();
+        sheet.write_to(&mut buf)?;
+        assert_eq!(buf, full.as_bytes());
+        Ok(())
+    }
+    #[test]
+    fn embedded_quotes() -> Result {
+        let mut sheet = CueSheet::default();
+        sheet.header.push_title(r#"Say "Hi""#.to_owned());
+        sheet.header.push_performer(r#""Weird Al" Yankovic"#.to_owned());
+        let written = sheet.to_string();
+        assert_eq!(
+            written,
+            "PERFORMER \"\"Weird Al\" Yankovic\"\nTITLE \"Say \"Hi\"\"\n"
+        );
+        assert_eq!(CueSheet::new(&written)?, sheet);
+        Ok(())
+    }
 }
 #[cfg(test)]
 mod parser {
@@ -179,3 +335,1241 @@ mod parser {
         Ok(())
     }
 }
+#[cfg(test)]
+mod cst {
+    use super::*;
+    use cuna::parser::cst::SyntaxTree;
+    use cuna::Cuna;
+
+    #[test]
+    fn lossless() -> Result {
+        let messy = "rem GENRE Pop\r\n\r\n  Title   \"Departures\"  \r\nfile \"a.flac\" wave\r\n\ttrack 01 audio\r\n    INDEX 01 00:00:00";
+        for cue in [CUE, include_str!("../benches/LACM-34919.cue"), messy] {
+            let tree = SyntaxTree::parse(cue);
+            assert_eq!(tree.to_string(), cue);
+            assert_eq!(tree.lower()?, Cuna::new(cue)?);
+        }
+        Ok(())
+    }
+    #[test]
+    fn edit() -> Result {
+        let mut tree = SyntaxTree::parse(CUE);
+        let track = tree.tracks_mut().nth(1).unwrap();
+        track.set("TITLE", "Euterpe");
+        track.set("ISRC", "JPI101200601");
+        tree.set("CATALOG", "4540774409198");
+        let printed = tree.to_string();
+        let changed: Vec<_> = printed
+            .lines()
+            .filter(|line| !CUE.lines().any(|l| l == *line))
+            .collect();
+        assert_eq!(
+            changed,
+            [
+                "CATALOG 4540774409198",
+                r#"    TITLE "Euterpe""#,
+                "    ISRC JPI101200601"
+            ]
+        );
+        assert_eq!(printed.lines().count(), CUE.lines().count() + 2);
+        let sheet = tree.lower()?;
+        assert_eq!(sheet[0][1].title(), &["Euterpe".to_owned()]);
+        assert_eq!(sheet[0][1].isrc(), Some("JPI101200601"));
+        Ok(())
+    }
+}
+#[cfg(test)]
+mod encoding {
+    use super::*;
+    use cuna::encoding::{detect, TextEncoding};
+    use cuna::Cuna;
+
+    const ZH: &str = r#"PERFORMER "周杰伦"
+TITLE "七里香"
+FILE "周杰伦 - 七里香.wav" WAVE
+  TRACK 01 AUDIO
+    TITLE "我的地盘"
+    INDEX 01 00:00:00
+  TRACK 02 AUDIO
+    TITLE "七里香"
+    INDEX 01 03:59:45
+"#;
+    const ZH_HANT: &str = r#"PERFORMER "周杰倫"
+TITLE "七里香"
+FILE "周杰倫 - 七里香.wav" WAVE
+  TRACK 01 AUDIO
+    TITLE "我的地盤"
+    INDEX 01 00:00:00
+  TRACK 02 AUDIO
+    TITLE "借口"
+    INDEX 01 03:59:45
+"#;
+
+    fn check(
+        text: &str,
+        encoding: &'static encoding_rs::Encoding,
+        expected: TextEncoding,
+    ) -> Result {
+        let text = cuna::trim_utf8_header(text);
+        let (bytes, _, unmappable) = encoding.encode(text);
+        assert!(!unmappable);
+        let (sheet, detected) = Cuna::from_bytes(&bytes)?;
+        assert_eq!(detected.encoding, expected);
+        assert_eq!(sheet, Cuna::new(text)?);
+        assert_eq!(Cuna::from_bytes_with_encoding(&bytes, expected)?, sheet);
+        Ok(())
+    }
+    #[test]
+    fn legacy() -> Result {
+        check(CUE, encoding_rs::SHIFT_JIS, TextEncoding::ShiftJis)?;
+        check(ZH, encoding_rs::GBK, TextEncoding::Gbk)?;
+        check(ZH_HANT, encoding_rs::BIG5, TextEncoding::Big5)?;
+        check(
+            "TITLE \"Café del Mar\"\nPERFORMER \"Beyoncé\"",
+            encoding_rs::WINDOWS_1252,
+            TextEncoding::Windows1252,
+        )?;
+        check(CUE, encoding_rs::UTF_8, TextEncoding::Utf8)
+    }
+    #[test]
+    fn utf16() -> Result {
+        let text = cuna::trim_utf8_header(CUE);
+        let le: Vec<u8> = text.encode_utf16().flat_map(u16::to_le_bytes).collect();
+        let be: Vec<u8> = text.encode_utf16().flat_map(u16::to_be_bytes).collect();
+        assert_eq!(detect(&le).encoding, TextEncoding::Utf16Le);
+        assert_eq!(detect(&be).encoding, TextEncoding::Utf16Be);
+        let zh = "TITLE \"一丁目の七里香、我的地盤と周杰倫の借口\"\nPERFORMER \"周杰倫\"";
+        let zh_le: Vec<u8> = zh.encode_utf16().flat_map(u16::to_le_bytes).collect();
+        let zh_be: Vec<u8> = zh.encode_utf16().flat_map(u16::to_be_bytes).collect();
+        assert_eq!(detect(&zh_le).encoding, TextEncoding::Utf16Le);
+        assert_eq!(detect(&zh_be).encoding, TextEncoding::Utf16Be);
+        assert_eq!(Cuna::from_bytes(&zh_le)?.0, Cuna::new(zh)?);
+        let with_bom = [&b"\xFF\xFE"[..], &le].concat();
+        let detected = detect(&with_bom);
+        assert!(detected.bom);
+        assert_eq!(detected.confidence, 1.0);
+        assert_eq!(Cuna::from_bytes(&with_bom)?.0, Cuna::new(text)?);
+        Ok(())
+    }
+    #[test]
+    fn malformed() {
+        let error =
+            Cuna::from_bytes_with_encoding(b"TITLE \"\xFF\"", TextEncoding::Utf8).unwrap_err();
+        assert_eq!(
+            error.kind(),
+            &cuna::error::ParseError::DecodeError(TextEncoding::Utf8)
+        );
+    }
+    #[test]
+    fn open() -> Result {
+        let path = "tests/EGOIST - Departures ～あなたにおくるアイの歌～.cue";
+        let (sheet, encoding) = Cuna::open_with_encoding(path, None)?;
+        assert_eq!(encoding, TextEncoding::Utf8);
+        assert_eq!(sheet, Cuna::open(path)?);
+        Ok(())
+    }
+}
+#[cfg(test)]
+mod comment {
+    use super::*;
+    use cuna::comment::{Comment, RemField};
+    use cuna::Cuna;
+
+    #[test]
+    fn fields() -> Result {
+        let sheet = Cuna::new(CUE)?;
+        let fields: Vec<_> = sheet.comments.fields().collect();
+        assert_eq!(
+            fields,
+            [
+                RemField::Genre("Pop".to_owned()),
+                RemField::Date("2011".parse().unwrap()),
+                RemField::DiscId(0x59063607),
+                RemField::Comment("ExactAudioCopy v0.99pb5".to_owned()),
+            ]
+        );
+        Ok(())
+    }
+    #[test]
+    fn set() {
+        let mut comments = Comment::new("REM DISCNUMBER 1\nREM REPLAYGAIN_ALBUM_PEAK 0.98");
+        assert_eq!(comments.disc_number(), Some(1));
+        assert_eq!(comments.total_discs(), None);
+        assert_eq!(
+            comments.set(RemField::DiscNumber(2)),
+            Some(RemField::DiscNumber(1))
+        );
+        assert_eq!(comments.set(RemField::TotalDiscs(2)), None);
+        comments.set(RemField::Composer("Ryo".to_owned()));
+        assert_eq!(
+            comments.0,
+            [
+                "DISCNUMBER 2",
+                "REPLAYGAIN_ALBUM_PEAK 0.98",
+                "TOTALDISCS 2",
+                "COMPOSER Ryo"
+            ]
+        );
+        assert_eq!(
+            RemField::new("REPLAYGAIN_ALBUM_PEAK 0.98").key(),
+            "REPLAYGAIN_ALBUM_PEAK"
+        );
+    }
+    #[test]
+    fn full_date() -> Result {
+        let field = RemField::new("DATE 2011/11/30");
+        assert_eq!(field.to_string(), "DATE 2011/11/30");
+        assert_eq!(
+            RemField::new("DATE 2011/11/31x"),
+            RemField::Other("DATE 2011/11/31x".to_owned())
+        );
+        let sheet = Cuna::new(
+            r#"FILE "a.wav" WAVE
+  TRACK 01 AUDIO
+    REM DATE 1998.03
+    INDEX 01 00:00:00"#,
+        )?;
+        let date = sheet[0][0].date().unwrap();
+        assert_eq!(
+            (date.year(), date.month(), date.day()),
+            (1998, Some(3), None)
+        );
+        assert_eq!(sheet.date(), None);
+        assert!(sheet.to_string().contains("    REM DATE 1998.03\n"));
+        Ok(())
+    }
+}
+#[cfg(test)]
+mod flag {
+    use super::*;
+    use cuna::error::InvalidArgument;
+    use cuna::error::ParseError;
+    use cuna::flag::{Flag, Flags};
+    use cuna::parser::{Command, ParseOptions, Parser};
+    use cuna::Cuna;
+
+    const SHEET: &str = r#"FILE "a.bin" BINARY
+  TRACK 01 AUDIO
+    FLAGS DCP  pre PRE4CH
+    INDEX 01 00:00:00"#;
+
+    #[test]
+    fn strict() {
+        let error = Cuna::new(SHEET).unwrap_err();
+        assert_eq!(error.pos(), Some(3));
+        assert_eq!(
+            error.kind(),
+            &ParseError::InvalidArgument(InvalidArgument::InvalidFlag)
+        );
+        let sheet = Cuna::new(&SHEET.replace(" PRE4CH", "")).unwrap();
+        let flags = sheet[0][0].flags();
+        assert!(flags.copy_permitted() && flags.pre_emphasis());
+        assert_eq!(flags.iter().collect::<Vec<_>>(), [Flag::Dcp, Flag::Pre]);
+        assert!(flags.unknown().is_empty());
+    }
+    #[test]
+    fn keep_unknown() -> Result {
+        let mut sheet = Cuna::default();
+        let options = ParseOptions {
+            keep_unknown_flags: true,
+            ..ParseOptions::default()
+        };
+        Parser::new(SHEET).with_options(options).parse(&mut sheet)?;
+        let flags = sheet[0][0].flags();
+        assert_eq!(flags.unknown(), ["PRE4CH"]);
+        assert_eq!(flags.to_string(), "DCP PRE PRE4CH");
+        assert!(sheet.to_string().contains("    FLAGS DCP PRE PRE4CH\n"));
+        let mut flags = Flags::new();
+        flags.insert(Flag::FourCh);
+        flags.insert(Flag::Data);
+        flags.remove(Flag::Data);
+        assert_eq!(flags, [Flag::FourCh].into_iter().collect());
+        Ok(())
+    }
+    #[test]
+    fn write() -> Result {
+        let command = Command::new("FLAGS 4CH SCMS")?;
+        assert_eq!(command.to_string(), "FLAGS 4CH SCMS");
+        let sheet = Cuna::new(SHEET.replace(" PRE4CH", "").as_str())?;
+        assert_eq!(Cuna::new(&sheet.to_string())?, sheet);
+        Ok(())
+    }
+}
+#[cfg(test)]
+mod mode {
+    use super::*;
+    use cuna::mode::TrackMode;
+    use cuna::Cuna;
+
+    #[test]
+    fn parse() -> Result {
+        let sheet = Cuna::new(
+            r#"FILE "game.bin" BINARY
+  TRACK 01 MODE1/2352
+    INDEX 01 00:00:00
+  TRACK 02 audio
+    INDEX 01 10:00:00
+  TRACK 03 MODE3/2048
+    INDEX 01 20:00:00"#,
+        )?;
+        let modes: Vec<_> = sheet.tracks().map(|tk| tk.format().clone()).collect();
+        assert_eq!(
+            modes,
+            [
+                TrackMode::Mode1_2352,
+                TrackMode::Audio,
+                TrackMode::Other("MODE3/2048".to_owned())
+            ]
+        );
+        assert!(modes[0].is_data() && modes[1].is_audio() && modes[2].is_other());
+        assert_eq!(modes[2].sector_size(), None);
+        assert!(sheet.to_string().contains("TRACK 02 AUDIO\n"));
+        assert_eq!(Cuna::new(&sheet.to_string())?, sheet);
+        let sizes: Vec<_> = TrackMode::STANDARD
+            .iter()
+            .map(|mode| mode.sector_size().unwrap())
+            .collect();
+        assert_eq!(
+            sizes,
+            [2352, 2448, 2048, 2352, 2048, 2324, 2336, 2352, 2336, 2352]
+        );
+        Ok(())
+    }
+}
+#[cfg(test)]
+mod file_type {
+    use super::*;
+    use cuna::file_type::FileType;
+    use cuna::parser::cst::SyntaxTree;
+    use cuna::Cuna;
+
+    #[test]
+    fn parse() -> Result {
+        let sheet = Cuna::new(
+            r#"FILE "01.flac"
+FILE "02 Title.wv"   WV
+FILE 03 Title.ape
+FILE 04.tak TAK
+FILE "05.BIN" MOTOROLA"#,
+        )?;
+        let types: Vec<_> = sheet.files().iter().map(|f| f.format.clone()).collect();
+        assert_eq!(
+            types,
+            [
+                FileType::Flac,
+                FileType::WavPack,
+                FileType::Ape,
+                FileType::Other("TAK".to_owned()),
+                FileType::Motorola
+            ]
+        );
+        assert_eq!(sheet[2].name, "03 Title.ape");
+        assert_eq!(sheet[3].name, "04.tak");
+        assert!(Cuna::new(r#"FILE "01.tak""#).is_err());
+        assert!(sheet.to_string().starts_with("FILE \"01.flac\" FLAC\n"));
+        Ok(())
+    }
+    #[test]
+    fn cst_name() {
+        let mut tree = SyntaxTree::parse("FILE 03 Title.ape\nFILE \"a.wav\"  WAVE\n");
+        let names: Vec<_> = tree.files().map(|f| f.name().to_owned()).collect();
+        assert_eq!(names, ["03 Title.ape", "a.wav"]);
+        tree.files_mut().for_each(|f| f.set_name("b.wav"));
+        assert_eq!(tree.to_string(), "FILE b.wav\nFILE \"b.wav\"  WAVE\n");
+    }
+}
+#[cfg(test)]
+mod catalog {
+    use super::*;
+    use cuna::catalog::Catalog;
+    use cuna::error::{InvalidArgument, ParseError};
+    use cuna::parser::{ParseOptions, Parser};
+    use cuna::Cuna;
+
+    #[test]
+    fn leading_zero() -> Result {
+        let sheet = Cuna::new("CATALOG 0000000000000\nTITLE \"Silence\"")?;
+        let catalog = sheet.catalog().unwrap();
+        assert_eq!(catalog.as_str(), "0000000000000");
+        assert!(sheet.to_string().starts_with("CATALOG 0000000000000\n"));
+        let placeholder = Cuna::new("CATALOG 4540774409199")?;
+        assert!(!placeholder.catalog().unwrap().is_valid());
+        let options = ParseOptions {
+            strict_catalog: true,
+            ..ParseOptions::default()
+        };
+        let error = Parser::new("CATALOG 4540774409199")
+            .with_options(options)
+            .parse(&mut Cuna::default())
+            .unwrap_err();
+        assert_eq!(
+            error.kind(),
+            &ParseError::InvalidArgument(InvalidArgument::InvalidCatalog)
+        );
+        Ok(())
+    }
+    #[test]
+    fn convert() -> std::result::Result<(), InvalidArgument> {
+        let upc: Catalog = "036000291452".parse()?;
+        assert!(upc.is_upc_a());
+        assert_eq!(upc.check_digit(), 2);
+        let ean = upc.to_ean13();
+        assert_eq!(ean.as_str(), "0036000291452");
+        assert!(ean.is_valid());
+        assert_eq!(ean.to_upc_a(), Some(upc));
+        let jp: Catalog = "4540774409198".parse()?;
+        assert_eq!(jp.to_upc_a(), None);
+        assert_eq!(jp.as_u64(), 4540774409198);
+        assert!("45407744091".parse::<Catalog>().is_err());
+        assert!(!Catalog::without_check_digit("4540774409199")?.is_valid());
+        Ok(())
+    }
+}
+#[cfg(test)]
+mod isrc {
+    use super::*;
+    use cuna::error::{InvalidArgument, ParseError};
+    use cuna::isrc::Isrc;
+    use cuna::parser::{ParseOptions, Parser};
+    use cuna::Cuna;
+
+    const BENCH: &str = include_str!("../benches/LACM-34919.cue");
+
+    #[test]
+    fn codes() -> Result {
+        let sheet = Cuna::new(BENCH)?;
+        let codes = sheet
+            .tracks()
+            .map(|tk| tk.isrc_code().map(Option::unwrap))
+            .collect::<std::result::Result<Vec<Isrc>, _>>()?;
+        assert_eq!(codes.len(), 6);
+        assert!(codes
+            .iter()
+            .all(|isrc| isrc.country() == "JP" && isrc.year() == 12));
+        assert_eq!(codes[5].designation(), 605);
+        assert_eq!(codes[5].hyphenated(), "JP-I10-12-00605");
+        Ok(())
+    }
+    #[test]
+    fn strict() -> Result {
+        let bad = BENCH.replace("ISRC JPI101200603", "ISRC JPI1012006O3");
+        let lenient = Cuna::new(&bad)?;
+        assert_eq!(lenient[0][3].isrc(), Some("JPI1012006O3"));
+        assert_eq!(lenient[0][3].isrc_code(), Err(InvalidArgument::InvalidIsrc));
+        let options = ParseOptions {
+            strict_isrc: true,
+            ..ParseOptions::default()
+        };
+        let mut sheet = Cuna::default();
+        Parser::new(cuna::trim_utf8_header(BENCH))
+            .with_options(options)
+            .parse(&mut sheet)?;
+        let error = Parser::new(cuna::trim_utf8_header(&bad))
+            .with_options(options)
+            .parse(&mut Cuna::default())
+            .unwrap_err();
+        assert_eq!(error.pos(), Some(27));
+        assert_eq!(
+            error.kind(),
+            &ParseError::InvalidArgument(InvalidArgument::InvalidIsrc)
+        );
+        Ok(())
+    }
+    #[test]
+    fn strict_normalized() -> Result {
+        let options = ParseOptions {
+            strict_isrc: true,
+            ..ParseOptions::default()
+        };
+        let cue = "FILE \"a.wav\" WAVE\n  TRACK 01 AUDIO\n    ISRC jp-i10-12-00600\n    INDEX 01 00:00:00";
+        let mut sheet = Cuna::default();
+        Parser::new(cue).with_options(options).parse(&mut sheet)?;
+        assert_eq!(sheet[0][0].isrc(), Some("JPI101200600"));
+        let written = sheet.to_string();
+        assert!(written.contains("ISRC JPI101200600\n"));
+        let mut reparsed = Cuna::default();
+        Parser::new(&written).with_options(options).parse(&mut reparsed)?;
+        assert_eq!(reparsed, sheet);
+        Ok(())
+    }
+    #[test]
+    fn stray_hyphens() {
+        for s in ["JP-I10-12-0-600", "JP-I-0-12-00600", "JP-I10-12-00600-"] {
+            assert_eq!(s.parse::<Isrc>(), Err(InvalidArgument::InvalidIsrc));
+        }
+    }
+}
+#[cfg(test)]
+mod sample {
+    use cuna::sample::SampleTime;
+    use cuna::time::{Rounding, TimeStamp};
+
+    #[test]
+    fn cd_frames() {
+        let timestamp = TimeStamp::new(4, 15, 44);
+        assert_eq!(timestamp.to_samples(44100), timestamp.total_frames_u64() * 588);
+        assert_eq!(timestamp.to_samples(96000), timestamp.total_frames_u64() * 1280);
+        let samples = timestamp.to_samples(44100);
+        for rounding in [Rounding::Floor, Rounding::Nearest, Rounding::Ceil] {
+            assert_eq!(
+                TimeStamp::from_samples(samples, 44100, rounding),
+                Ok(timestamp)
+            );
+        }
+    }
+    #[test]
+    fn sub_frame() {
+        let split = SampleTime::new(48000 + 1000, 48000);
+        assert_eq!(split.frame_offset(), 360);
+        assert_eq!(
+            split.to_timestamp(Rounding::Nearest),
+            Ok(TimeStamp::new(0, 1, 2))
+        );
+        assert_eq!(split.to_rate(96000, Rounding::Floor).samples(), 98000);
+        assert_eq!(split.to_duration().as_micros(), 1_020_833);
+        assert_eq!(split.to_string(), "49000@48000Hz");
+    }
+}
+#[cfg(test)]
+mod address {
+    use cuna::address::{AbsoluteMsf, Lba};
+    use cuna::time::TimeOffset;
+
+    #[test]
+    fn round_trip() {
+        for lba in (-45150..=404_849)
+            .step_by(97)
+            .chain([-45150, -151, -150, 404_849])
+        {
+            let msf = Lba(lba).to_msf().unwrap();
+            assert_eq!(msf.to_lba(), Lba(lba));
+        }
+        assert_eq!(Lba(-45151).to_msf(), None);
+        assert_eq!(Lba(404_850).to_msf(), None);
+        assert_eq!(AbsoluteMsf::new(90, 0, 0).to_lba(), Lba(-45150));
+        assert_eq!(AbsoluteMsf::new(0, 2, 0).to_lba(), Lba(0));
+    }
+    #[test]
+    fn pregap() {
+        let start = Lba(0) + TimeOffset::from_frames(-150);
+        assert_eq!(start, Lba(-150));
+        assert_eq!(start.to_timestamp(), None);
+        assert_eq!(start.to_msf().unwrap().to_string(), "00:00:00");
+        assert_eq!(Lba(0) - start, TimeOffset::from_frames(150));
+    }
+}
+#[cfg(test)]
+mod layout {
+    use super::*;
+    use cuna::address::Lba;
+    use cuna::error::LayoutError;
+    use cuna::time::TimeStamp;
+    use cuna::CueSheet;
+
+    const MULTI: &str = r#"FILE "01.wav" WAVE
+  TRACK 01 AUDIO
+    INDEX 01 00:00:00
+  TRACK 02 AUDIO
+    INDEX 00 04:58:00
+FILE "02.wav" WAVE
+    INDEX 01 00:00:00
+FILE "03.wav" WAVE
+  TRACK 03 AUDIO
+    PREGAP 00:02:00
+    INDEX 01 00:00:00
+    INDEX 02 01:00:00
+    POSTGAP 00:01:00
+"#;
+
+    #[test]
+    fn multi_file() -> Result {
+        let sheet = CueSheet::new(MULTI)?;
+        assert_eq!(sheet.files[1].continued().len(), 1);
+        assert_eq!(sheet.to_string(), MULTI);
+        let lengths = [
+            TimeStamp::new(5, 0, 0),
+            TimeStamp::new(3, 0, 0),
+            TimeStamp::new(2, 0, 0),
+        ];
+        let layout = sheet.layout(&lengths).unwrap();
+        assert_eq!(layout.files, vec![Lba(0), Lba(22500), Lba(36000)]);
+        let first = &layout.tracks[0];
+        assert_eq!((first.index00, first.index01), (None, Lba(0)));
+        assert_eq!(first.length, TimeStamp::new(5, 0, 0));
+        let second = &layout.tracks[1];
+        assert_eq!(second.start(), Lba(22350));
+        assert_eq!(second.pregap(), TimeStamp::new(0, 2, 0));
+        assert_eq!(second.length, TimeStamp::new(3, 2, 0));
+        let third = &layout.tracks[2];
+        assert_eq!(third.index00, Some(Lba(36000)));
+        assert_eq!(third.index01, Lba(36150));
+        assert_eq!(third.index(2), Some(Lba(40650)));
+        assert_eq!(layout.lead_out, Lba(45225));
+        assert_eq!(third.length, TimeStamp::new(2, 1, 0));
+        Ok(())
+    }
+    #[test]
+    fn errors() -> Result {
+        let sheet = CueSheet::new(MULTI)?;
+        assert_eq!(
+            sheet.layout(&[TimeStamp::ZERO]),
+            Err(LayoutError::FileCount {
+                expected: 3,
+                found: 1
+            })
+        );
+        let short = [
+            TimeStamp::new(4, 0, 0),
+            TimeStamp::new(3, 0, 0),
+            TimeStamp::new(2, 0, 0),
+        ];
+        assert_eq!(sheet.layout(&short), Err(LayoutError::IndexOutOfFile(2)));
+        Ok(())
+    }
+}
+#[cfg(test)]
+mod length {
+    use super::*;
+    use cuna::audio::{AudioInfo, CddaMismatch, Container, SampleFormat};
+    use cuna::length::LocalFiles;
+    use cuna::time::TimeStamp;
+    use cuna::CueSheet;
+    use std::fs;
+
+    fn flac(samples: u64) -> Vec<u8> {
+        let mut flac = b"fLaC\x80\0\0\x22".to_vec();
+        flac.extend([0; 10]);
+        flac.extend((44100 << 44 | 1 << 41 | 15 << 36 | samples).to_be_bytes());
+        flac.extend([0; 16]);
+        flac
+    }
+
+    #[test]
+    fn headers() {
+        let info = AudioInfo::read(std::io::Cursor::new(aiff_header(44100))).unwrap();
+        assert_eq!(info.container, Container::Aiff);
+        assert_eq!((info.sample_rate, info.channels), (44100, 2));
+        let info = AudioInfo::read(std::io::Cursor::new(flac(589))).unwrap();
+        assert_eq!(info.container, Container::Flac);
+        assert_eq!((info.bits_per_sample, info.samples), (16, 589));
+    }
+    #[test]
+    fn extended_formats() {
+        // WAVE_FORMAT_EXTENSIBLE in RF64, 96 kHz 24-bit float
+        let mut rf64 = b"RF64\xff\xff\xff\xffWAVEds64\x1c\0\0\0".to_vec();
+        rf64.extend([0; 8]);
+        rf64.extend((96000u64 * 6 * 2).to_le_bytes());
+        rf64.extend([0; 12]);
+        rf64.extend(b"fmt \x28\0\0\0\xfe\xff\x02\0\x00\x77\x01\0\0\0\0\0\x06\0\x18\0");
+        rf64.extend([22, 0, 24, 0, 3, 0, 0, 0, 3, 0, 0, 0]);
+        rf64.extend([0; 12]);
+        rf64.extend(b"data\xff\xff\xff\xff");
+        let info = AudioInfo::read(std::io::Cursor::new(rf64)).unwrap();
+        assert_eq!(info.format, SampleFormat::Float);
+        assert_eq!((info.sample_rate, info.samples), (96000, 192000));
+        assert_eq!(
+            info.cdda_mismatches(),
+            vec![
+                CddaMismatch::SampleRate(96000),
+                CddaMismatch::BitsPerSample(24),
+                CddaMismatch::Format(SampleFormat::Float)
+            ]
+        );
+        // little-endian AIFF-C
+        let mut aifc =
+            b"FORM\0\0\0\0AIFCFVER\0\0\0\x04\xa2\x80\x51\x40COMM\0\0\0\x18\0\x02".to_vec();
+        aifc.extend(588u32.to_be_bytes());
+        aifc.extend([0, 16, 0x40, 0x0e, 0xac, 0x44, 0, 0, 0, 0, 0, 0]);
+        aifc.extend(b"sowt");
+        let info = AudioInfo::read(std::io::Cursor::new(aifc)).unwrap();
+        assert_eq!(info.container, Container::Aiff);
+        assert!(info.is_cdda());
+    }
+    #[test]
+    fn local_files() {
+        let dir = std::env::temp_dir().join(format!("cuna-length-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("1.wav"), wav_header(88200)).unwrap();
+        fs::write(dir.join("2.aiff"), aiff_header(44100)).unwrap();
+        fs::write(dir.join("3.flac"), flac(589)).unwrap();
+        fs::write(dir.join("4.bin"), vec![0; 2352 * 10 + 1]).unwrap();
+        fs::write(dir.join("5.bin"), vec![0; 2352 * 10]).unwrap();
+        let sheet = CueSheet::new(
+            r#"FILE "1.wav" WAVE
+  TRACK 01 AUDIO
+    INDEX 01 00:00:00
+  TRACK 02 AUDIO
+    INDEX 01 00:01:00
+FILE "2.aiff" AIFF
+  TRACK 03 AUDIO
+    INDEX 01 00:00:00
+FILE "3.flac" WAVE
+  TRACK 04 AUDIO
+    INDEX 01 00:00:00
+FILE "4.bin" BINARY
+  TRACK 05 AUDIO
+    INDEX 01 00:00:00"#,
+        )
+        .unwrap();
+        let durations = sheet
+            .track_durations(&LocalFiles::beside(dir.join("a.cue")))
+            .unwrap();
+        let check = LocalFiles::new(&dir).check(&sheet);
+        let untracked = CueSheet::new(r#"FILE "5.bin" BINARY"#).unwrap();
+        let untracked = untracked.file_lengths(&LocalFiles::new(&dir)).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(untracked, vec![TimeStamp::from_frames(10)]);
+        let mismatches: Vec<_> = check.into_iter().map(|(i, r)| (i, r.unwrap())).collect();
+        assert_eq!(
+            mismatches,
+            vec![
+                (0, vec![]),
+                (1, vec![]),
+                (2, vec![CddaMismatch::PartialFrame(1)])
+            ]
+        );
+        assert_eq!(
+            durations,
+            vec![
+                TimeStamp::new(0, 1, 0),
+                TimeStamp::new(0, 1, 0),
+                TimeStamp::new(0, 1, 0),
+                TimeStamp::from_frames(2),
+                TimeStamp::from_frames(11),
+            ]
+        );
+        let missing = sheet.track_durations(&LocalFiles::new(dir));
+        assert!(missing.is_err());
+    }
+}
+#[cfg(test)]
+mod image {
+    use cuna::error::{ImageError, LayoutError};
+    use cuna::image::ImageLayout;
+    use cuna::mode::TrackMode;
+    use cuna::time::TimeStamp;
+    use cuna::CueSheet;
+    use std::fs;
+
+    const MIXED: &str = r#"FILE "disc.bin" BINARY
+  TRACK 01 MODE2/2336
+    INDEX 01 00:00:00
+  TRACK 02 MODE1/2048
+    INDEX 01 00:01:00
+  TRACK 03 AUDIO
+    INDEX 00 00:02:00
+    INDEX 01 00:04:00
+FILE "extra.bin" BINARY
+  TRACK 04 AUDIO
+    INDEX 01 00:00:00
+"#;
+
+    #[test]
+    fn mixed_mode() {
+        let sheet = CueSheet::new(MIXED).unwrap();
+        let sizes = [75 * 2336 + 75 * 2048 + 150 * 2352, 10 * 2352];
+        let image = ImageLayout::new(&sheet, &sizes).unwrap();
+        let ranges: Vec<_> = image.tracks.iter().map(|t| t.bytes.clone()).collect();
+        assert_eq!(
+            ranges,
+            vec![0..175_200, 175_200..328_800, 328_800..681_600, 0..23_520]
+        );
+        let track = image.track(3).unwrap();
+        assert_eq!(track.mode, TrackMode::Audio);
+        assert_eq!(track.start, TimeStamp::new(0, 2, 0));
+        assert_eq!(track.length(), TimeStamp::new(0, 2, 0));
+        assert_eq!(image.track(4).unwrap().file, 1);
+    }
+    #[test]
+    fn errors() {
+        let sheet = CueSheet::new(MIXED).unwrap();
+        let sizes = [75 * 2336 + 75 * 2048 + 150 * 2352 + 1, 10 * 2352];
+        assert_eq!(
+            ImageLayout::new(&sheet, &sizes),
+            Err(LayoutError::PartialSector(3))
+        );
+        let sizes = [75 * 2336, 10 * 2352];
+        assert_eq!(
+            ImageLayout::new(&sheet, &sizes),
+            Err(LayoutError::IndexOutOfFile(3))
+        );
+    }
+    #[test]
+    fn open() {
+        let sheet = CueSheet::new(MIXED).unwrap();
+        let dir = std::env::temp_dir().join(format!("cuna-image-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("disc.bin"), vec![0; 75 * 2336 + 75 * 2048 + 150 * 2352]).unwrap();
+        let missing = ImageLayout::open(&sheet, &dir);
+        fs::write(dir.join("extra.bin"), vec![0; 10 * 2352 + 1]).unwrap();
+        let partial = ImageLayout::open(&sheet, &dir);
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(matches!(missing, Err(ImageError::IoError(_))));
+        assert!(matches!(
+            partial,
+            Err(ImageError::Layout(LayoutError::PartialSector(4)))
+        ));
+    }
+}
+#[cfg(test)]
+mod gap {
+    use super::*;
+    use cuna::error::{GapError, LayoutError};
+    use cuna::gap::{AssumeSilent, GapEdit, GapStyle, SilenceProbe};
+    use cuna::length::LocalFiles;
+    use cuna::time::TimeStamp;
+    use cuna::track::Disc;
+    use cuna::CueSheet;
+    use std::fs;
+    use std::ops::Range;
+
+    const APPENDED: &str = r#"FILE "01.wav" WAVE
+  TRACK 01 AUDIO
+    INDEX 00 00:00:00
+    INDEX 01 00:01:00
+  TRACK 02 AUDIO
+    TITLE "Second"
+    INDEX 00 03:58:00
+FILE "02.wav" WAVE
+    INDEX 01 00:00:00
+    INDEX 02 01:00:00
+FILE "03.wav" WAVE
+  TRACK 03 AUDIO
+    INDEX 01 00:00:00
+"#;
+
+    #[test]
+    fn convert() {
+        let sheet = CueSheet::new(APPENDED).unwrap();
+        let lengths = [
+            TimeStamp::new(4, 0, 0),
+            TimeStamp::new(3, 0, 0),
+            TimeStamp::new(2, 0, 0),
+        ];
+        let (prepended, prepended_lengths) =
+            sheet.convert_gaps(&lengths, GapStyle::Prepended).unwrap();
+        assert_eq!(
+            prepended.to_string(),
+            r#"FILE "01.wav" WAVE
+  TRACK 01 AUDIO
+    INDEX 00 00:00:00
+    INDEX 01 00:01:00
+FILE "02.wav" WAVE
+  TRACK 02 AUDIO
+    TITLE "Second"
+    INDEX 00 00:00:00
+    INDEX 01 00:02:00
+    INDEX 02 01:02:00
+FILE "03.wav" WAVE
+  TRACK 03 AUDIO
+    INDEX 01 00:00:00
+"#
+        );
+        assert_eq!(
+            prepended_lengths,
+            vec![
+                TimeStamp::new(3, 58, 0),
+                TimeStamp::new(3, 2, 0),
+                TimeStamp::new(2, 0, 0)
+            ]
+        );
+        let (left_out, left_out_lengths) = prepended
+            .convert_gaps(&prepended_lengths, GapStyle::LeftOut)
+            .unwrap();
+        assert_eq!(left_out[1][0].pregap(), Some(&TimeStamp::new(0, 2, 0)));
+        assert_eq!(left_out_lengths[1], TimeStamp::new(3, 0, 0));
+        let (appended, appended_lengths) = left_out
+            .convert_gaps(&left_out_lengths, GapStyle::Appended)
+            .unwrap();
+        assert_eq!(appended.to_string(), APPENDED);
+        assert_eq!(appended_lengths, lengths);
+    }
+    #[test]
+    fn stored_and_generated() {
+        let sheet = CueSheet::new(
+            r#"FILE "01.wav" WAVE
+  TRACK 01 AUDIO
+    INDEX 01 00:00:00
+FILE "02.wav" WAVE
+  TRACK 02 AUDIO
+    PREGAP 00:01:00
+    INDEX 00 00:00:00
+    INDEX 01 00:02:00"#,
+        )
+        .unwrap();
+        let lengths = [TimeStamp::new(4, 0, 0), TimeStamp::new(3, 0, 0)];
+        let (prepended, prepended_lengths) =
+            sheet.convert_gaps(&lengths, GapStyle::Prepended).unwrap();
+        assert_eq!(prepended[1][0].pregap(), None);
+        assert_eq!(prepended[1][0].index()[1].begin_time, TimeStamp::new(0, 3, 0));
+        assert_eq!(prepended_lengths[1], TimeStamp::new(3, 1, 0));
+        let (left_out, left_out_lengths) =
+            sheet.convert_gaps(&lengths, GapStyle::LeftOut).unwrap();
+        assert_eq!(left_out[1][0].pregap(), Some(&TimeStamp::new(0, 3, 0)));
+        assert_eq!(left_out_lengths[1], TimeStamp::new(2, 58, 0));
+        let (_, appended_lengths) = sheet.convert_gaps(&lengths, GapStyle::Appended).unwrap();
+        assert_eq!(
+            appended_lengths,
+            vec![TimeStamp::new(4, 3, 0), TimeStamp::new(2, 58, 0)]
+        );
+    }
+    #[test]
+    fn enhanced() {
+        let sheet = CueSheet::new(
+            r#"FILE "01.wav" WAVE
+  TRACK 01 AUDIO
+    INDEX 01 00:00:00
+FILE "02.wav" WAVE
+  TRACK 02 AUDIO
+    INDEX 00 00:00:00
+    INDEX 01 00:02:00
+REM SESSION 02
+FILE "03.bin" BINARY
+  TRACK 03 MODE1/2352
+    INDEX 01 00:00:00"#,
+        )
+        .unwrap();
+        let lengths = [
+            TimeStamp::new(4, 0, 0),
+            TimeStamp::new(2, 2, 0),
+            TimeStamp::new(1, 0, 0),
+        ];
+        let durations = sheet.track_durations(&lengths[..]).unwrap();
+        assert_eq!(durations[1], TimeStamp::new(2, 0, 0));
+        let (appended, appended_lengths) =
+            sheet.convert_gaps(&lengths, GapStyle::Appended).unwrap();
+        assert_eq!(
+            appended_lengths,
+            vec![
+                TimeStamp::new(4, 2, 0),
+                TimeStamp::new(2, 0, 0),
+                TimeStamp::new(1, 0, 0)
+            ]
+        );
+        assert_eq!(appended.sessions()[1].tracks[0].id(), 3);
+        let (_, prepended_lengths) = appended
+            .convert_gaps(&appended_lengths, GapStyle::Prepended)
+            .unwrap();
+        assert_eq!(prepended_lengths, lengths);
+    }
+    #[test]
+    fn normalize() {
+        let original = r#"FILE "a.wav" WAVE
+  TRACK 01 AUDIO
+    INDEX 01 00:00:00
+  TRACK 02 AUDIO
+    INDEX 00 03:00:00
+    INDEX 01 03:02:00
+  TRACK 03 AUDIO
+    INDEX 00 05:00:00
+    INDEX 01 05:01:00
+"#;
+        let mut sheet = CueSheet::new(original).unwrap();
+        let noisy = |_: usize, _: &Disc, range: Range<TimeStamp>| {
+            Ok(range.start != TimeStamp::new(5, 0, 0))
+        };
+        let report = sheet.index00_to_pregap(&noisy).unwrap();
+        assert_eq!(report.not_silent, vec![3]);
+        assert_eq!(sheet[0][1].pregap(), Some(&TimeStamp::new(0, 2, 0)));
+        assert_eq!(sheet[0][2].index()[0].begin_time, TimeStamp::new(4, 58, 0));
+        let report = sheet.pregap_to_index00().unwrap();
+        assert_eq!(
+            report.edits,
+            vec![GapEdit::Insert {
+                file: 0,
+                at: Some(TimeStamp::new(3, 0, 0)),
+                length: TimeStamp::new(0, 2, 0)
+            }]
+        );
+        assert_eq!(sheet.to_string(), original);
+    }
+    #[test]
+    fn postgap() {
+        let mut sheet = CueSheet::new(
+            r#"FILE "a.wav" WAVE
+  TRACK 01 AUDIO
+    INDEX 01 00:00:00
+  TRACK 02 AUDIO
+    INDEX 01 03:00:00"#,
+        )
+        .unwrap();
+        let lengths = vec![TimeStamp::new(5, 0, 0)];
+        let report = sheet
+            .audio_to_postgap(1, TimeStamp::new(0, 1, 0), &lengths, &AssumeSilent)
+            .unwrap();
+        assert_eq!(report.not_silent, vec![]);
+        assert_eq!(sheet[0][0].postgap(), Some(&TimeStamp::new(0, 1, 0)));
+        assert_eq!(sheet[0][1].index()[0].begin_time, TimeStamp::new(2, 59, 0));
+        sheet
+            .audio_to_postgap(2, TimeStamp::new(0, 3, 0), &lengths, &AssumeSilent)
+            .unwrap();
+        assert_eq!(sheet[0][1].postgap(), Some(&TimeStamp::new(0, 3, 0)));
+        assert!(matches!(
+            sheet.audio_to_postgap(2, TimeStamp::new(9, 0, 0), &lengths, &AssumeSilent),
+            Err(GapError::TooLong(2))
+        ));
+        assert!(matches!(
+            sheet.audio_to_postgap(7, TimeStamp::new(0, 1, 0), &lengths, &AssumeSilent),
+            Err(GapError::NoSuchTrack(7))
+        ));
+        let report = sheet.postgap_to_audio().unwrap();
+        assert_eq!(report.edits.len(), 2);
+        assert_eq!(sheet[0][1].index()[0].begin_time, TimeStamp::new(3, 0, 0));
+        assert_eq!(sheet[0][1].postgap(), None);
+        sheet.files[0].tracks[0].set_postgep(TimeStamp::MAX);
+        assert!(matches!(
+            sheet.postgap_to_audio(),
+            Err(GapError::OutOfRange(_))
+        ));
+        assert_eq!(sheet[0][0].postgap(), Some(&TimeStamp::MAX));
+        assert_eq!(sheet[0][1].index()[0].begin_time, TimeStamp::new(3, 0, 0));
+    }
+    #[test]
+    fn continued_postgap() {
+        let original = CueSheet::new(
+            r#"FILE "01.wav" WAVE
+  TRACK 01 AUDIO
+    INDEX 01 00:00:00
+  TRACK 02 AUDIO
+    INDEX 00 03:58:00
+FILE "02.wav" WAVE
+    INDEX 01 00:00:00
+  TRACK 03 AUDIO
+    INDEX 01 03:00:00"#,
+        )
+        .unwrap();
+        let mut sheet = original.clone();
+        let lengths = vec![TimeStamp::new(4, 0, 0), TimeStamp::new(5, 0, 0)];
+        let report = sheet
+            .audio_to_postgap(2, TimeStamp::new(0, 1, 0), &lengths, &AssumeSilent)
+            .unwrap();
+        assert_eq!(
+            report.edits,
+            vec![GapEdit::Remove {
+                file: 1,
+                at: TimeStamp::new(2, 59, 0),
+                length: TimeStamp::new(0, 1, 0)
+            }]
+        );
+        assert_eq!(sheet[0][1].index()[0].begin_time, TimeStamp::new(3, 58, 0));
+        assert_eq!(sheet[1][0].index()[0].begin_time, TimeStamp::new(2, 59, 0));
+        let report = sheet.postgap_to_audio().unwrap();
+        assert_eq!(
+            report.edits,
+            vec![GapEdit::Insert {
+                file: 1,
+                at: Some(TimeStamp::new(2, 59, 0)),
+                length: TimeStamp::new(0, 1, 0)
+            }]
+        );
+        assert_eq!(sheet, original);
+    }
+    #[test]
+    fn local_files() {
+        let frames = |fill: &[u8]| -> Vec<u8> { fill.iter().flat_map(|&b| [b; 588 * 4]).collect() };
+        let dir = std::env::temp_dir().join(format!("cuna-gap-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let mut wav = wav_header(588 * 4);
+        wav.extend(frames(&[1, 1, 0, 1]));
+        fs::write(dir.join("a.wav"), wav).unwrap();
+        let mut aiff = aiff_header(588 * 2);
+        aiff.extend(b"SSND");
+        aiff.extend((8 + 588 * 2 * 4u32).to_be_bytes());
+        aiff.extend([0; 8]);
+        aiff.extend(frames(&[0, 1]));
+        fs::write(dir.join("b.aiff"), aiff).unwrap();
+        let bin: Vec<u8> = [0, 0, 1, 1, 1].iter().flat_map(|&b| [b; 2352]).collect();
+        fs::write(dir.join("c.bin"), bin).unwrap();
+        let mut sheet = CueSheet::new(
+            r#"FILE "a.wav" WAVE
+  TRACK 01 AUDIO
+    INDEX 01 00:00:00
+  TRACK 02 AUDIO
+    INDEX 00 00:00:02
+    INDEX 01 00:00:03
+FILE "b.aiff" AIFF
+  TRACK 03 AUDIO
+    INDEX 00 00:00:00
+    INDEX 01 00:00:01
+FILE "c.bin" BINARY
+  TRACK 04 AUDIO
+    INDEX 00 00:00:00
+    INDEX 01 00:00:02
+  TRACK 05 AUDIO
+    INDEX 00 00:00:03
+    INDEX 01 00:00:04"#,
+        )
+        .unwrap();
+        let files = LocalFiles::new(&dir);
+        let frame = TimeStamp::from_frames;
+        let noisy = files.is_silent(0, &sheet[0], frame(1)..frame(3));
+        let report = sheet.index00_to_pregap(&files);
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(!noisy.unwrap());
+        let report = report.unwrap();
+        assert_eq!(report.not_silent, vec![5]);
+        assert_eq!(report.edits.len(), 3);
+        assert_eq!(sheet[2][0].pregap(), Some(&frame(2)));
+    }
+    #[test]
+    fn report() {
+        let sheet = CueSheet::new(APPENDED).unwrap();
+        let gaps = sheet.gaps();
+        assert_eq!(gaps.len(), 3);
+        assert_eq!(gaps[0].stored, Some(TimeStamp::new(0, 1, 0)));
+        assert!(!gaps[0].crosses_file);
+        assert!(gaps[1].crosses_file);
+        assert_eq!(gaps[1].sub_indexes.len(), 1);
+        assert_eq!(gaps[1].pregap(), None);
+        assert_eq!(gaps[2].pregap(), Some(TimeStamp::ZERO));
+        assert_eq!(gaps[2].postgap, None);
+        let lengths = vec![TimeStamp::new(4, 0, 0)];
+        let measured = sheet.gaps_with_lengths(&lengths).unwrap();
+        assert_eq!(measured[1].stored, Some(TimeStamp::new(0, 2, 0)));
+        assert_eq!(measured[1].pregap(), Some(TimeStamp::new(0, 2, 0)));
+        assert_eq!(measured[0], gaps[0]);
+        assert!(sheet.gaps_with_lengths(&Vec::new()).is_err());
+    }
+    #[test]
+    fn single_file() {
+        let sheet = CueSheet::new(super::CUE).unwrap();
+        let lengths = [TimeStamp::new(60, 0, 0)];
+        assert_eq!(
+            sheet.convert_gaps(&lengths, GapStyle::Prepended),
+            Err(LayoutError::TrackPerFile(0))
+        );
+        let mixed = CueSheet::new(
+            r#"FILE "01.wav" WAVE
+  TRACK 01 AUDIO
+    INDEX 01 00:00:00
+FILE "02-03.wav" WAVE
+  TRACK 02 AUDIO
+    INDEX 01 00:00:00
+  TRACK 03 AUDIO
+    INDEX 00 02:58:00
+    INDEX 01 03:00:00"#,
+        )
+        .unwrap();
+        let lengths = [TimeStamp::new(4, 0, 0), TimeStamp::new(6, 0, 0)];
+        assert_eq!(
+            mixed.convert_gaps(&lengths, GapStyle::Appended),
+            Err(LayoutError::TrackPerFile(1))
+        );
+    }
+}
+#[cfg(test)]
+mod htoa {
+    use cuna::gap::AssumeSilent;
+    use cuna::time::TimeStamp;
+    use cuna::track::Disc;
+    use cuna::CueSheet;
+    use std::ops::Range;
+
+    fn noisy(_: usize, _: &Disc, _: Range<TimeStamp>) -> std::io::Result<bool> {
+        Ok(false)
+    }
+
+    #[test]
+    fn split() {
+        let mut sheet = CueSheet::new(
+            r#"FILE "a.wav" WAVE
+  TRACK 01 AUDIO
+    TITLE "First"
+    INDEX 00 00:00:00
+    INDEX 01 01:02:03
+  TRACK 02 AUDIO
+    INDEX 01 04:00:00"#,
+        )
+        .unwrap();
+        let htoa = sheet.split_htoa(&noisy).unwrap().unwrap();
+        assert_eq!((htoa.file, htoa.length()), (0, TimeStamp::new(1, 2, 3)));
+        let reparsed = CueSheet::new(&sheet.to_string()).unwrap();
+        assert_eq!(reparsed, sheet);
+        let durations = sheet
+            .track_durations(&[TimeStamp::new(6, 0, 0)][..])
+            .unwrap();
+        assert_eq!(durations[0], TimeStamp::new(1, 2, 3));
+        assert_eq!(sheet[0][1].title(), &["First".to_owned()]);
+    }
+    #[test]
+    fn none() {
+        let sheet = CueSheet::new(super::CUE).unwrap();
+        assert_eq!(sheet.htoa(&noisy).unwrap(), None);
+        // a silent pregap of track 1
+        let pregap = CueSheet::new(
+            r#"FILE "a.wav" WAVE
+  TRACK 01 AUDIO
+    INDEX 00 00:00:00
+    INDEX 01 00:02:00"#,
+        )
+        .unwrap();
+        assert_eq!(pregap.htoa(&AssumeSilent).unwrap(), None);
+        assert!(pregap.htoa(&noisy).unwrap().is_some());
+        // a sheet which does not start at track 1
+        let later = CueSheet::new(
+            r#"FILE "b.wav" WAVE
+  TRACK 05 AUDIO
+    INDEX 00 00:00:00
+    INDEX 01 00:02:00"#,
+        )
+        .unwrap();
+        assert_eq!(later.htoa(&noisy).unwrap(), None);
+    }
+}
+#[cfg(test)]
+mod session {
+    use cuna::address::Lba;
+    use cuna::comment::RemField;
+    use cuna::time::TimeStamp;
+    use cuna::CueSheet;
+
+    const ENHANCED: &str = r#"REM SESSION 01
+FILE "Range.wav" WAVE
+  TRACK 01 AUDIO
+    INDEX 01 00:00:00
+  TRACK 02 AUDIO
+    INDEX 01 03:00:00
+REM SESSION 02
+FILE "Data.bin" BINARY
+  TRACK 03 MODE1/2352
+    INDEX 01 00:00:00"#;
+
+    #[test]
+    fn sessions() {
+        let sheet = CueSheet::new(ENHANCED).unwrap();
+        let sessions = sheet.sessions();
+        let numbers: Vec<_> = sessions.iter().map(|s| s.number).collect();
+        assert_eq!(numbers, vec![1, 2]);
+        assert_eq!(sessions[0].tracks.len(), 2);
+        assert_eq!(sheet[0][1].comments().session(), Some(2));
+        assert_eq!(RemField::new("SESSION 02"), RemField::Session(2));
+        assert_eq!(RemField::Session(2).to_string(), "SESSION 02");
+        assert_eq!(CueSheet::new(super::CUE).unwrap().sessions().len(), 1);
+    }
+    #[test]
+    fn layout() {
+        let lengths = [TimeStamp::new(5, 0, 0), TimeStamp::new(1, 0, 0)];
+        let layout = CueSheet::new(ENHANCED).unwrap().layout(&lengths).unwrap();
+        let sessions: Vec<_> = layout
+            .sessions
+            .iter()
+            .map(|s| (s.number, s.first_track, s.lead_out))
+            .collect();
+        assert_eq!(sessions, vec![(1, 1, Lba(22500)), (2, 3, Lba(38400))]);
+        assert_eq!(layout.session_lead_out(1), Some(Lba(22500)));
+        let data = layout.track(3).unwrap();
+        assert_eq!((data.session, data.index01), (2, Lba(33900)));
+        assert_eq!(layout.track(2).unwrap().length, TimeStamp::new(2, 0, 0));
+        assert_eq!(layout.cddb_id(), 0x1a02_0003);
+        assert_eq!(layout.files, vec![Lba(0), Lba(33900)]);
+
+        let with_pregap = ENHANCED.replace("MODE1/2352", "MODE1/2352\n    PREGAP 00:02:00");
+        let layout = CueSheet::new(&with_pregap)
+            .unwrap()
+            .layout(&lengths)
+            .unwrap();
+        let data = layout.track(3).unwrap();
+        assert_eq!(data.index00, Some(Lba(33750)));
+        assert_eq!(data.index01, Lba(33900));
+    }
+}